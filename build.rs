@@ -1,7 +1,8 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_server(true)
-        .build_client(false)
+        .build_client(true)
+        .build_transport(false)
         .compile_protos(
             &[
                 "proto/apps/v0/common.proto",
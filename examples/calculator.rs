@@ -1,55 +1,49 @@
 use async_trait::async_trait;
 use nebo_sdk::error::NeboError;
-use nebo_sdk::schema::SchemaBuilder;
-use nebo_sdk::tool::ToolHandler;
-use nebo_sdk::NeboApp;
+use nebo_sdk::tool::TypedToolHandler;
+use nebo_sdk::{nebo_tool, NeboApp, NeboSchema, ToolContext, ToolOutput};
 use serde::Deserialize;
-use serde_json::Value;
 
 struct Calculator;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, NeboSchema)]
+#[serde(rename_all = "snake_case")]
+enum Action {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Deserialize, NeboSchema)]
 struct CalcInput {
-    action: String,
+    action: Action,
+    /// First operand
     a: f64,
+    /// Second operand
     b: f64,
 }
 
+/// Performs arithmetic calculations.
+#[nebo_tool(name = "calculator")]
 #[async_trait]
-impl ToolHandler for Calculator {
-    fn name(&self) -> &str {
-        "calculator"
-    }
-
-    fn description(&self) -> &str {
-        "Performs arithmetic calculations."
-    }
-
-    fn schema(&self) -> Value {
-        SchemaBuilder::new(&["add", "subtract", "multiply", "divide"])
-            .number("a", "First operand", true)
-            .number("b", "Second operand", true)
-            .build()
-    }
-
-    async fn execute(&self, input: Value) -> Result<String, NeboError> {
-        let inp: CalcInput =
-            serde_json::from_value(input).map_err(|e| NeboError::Execution(e.to_string()))?;
-
-        let result = match inp.action.as_str() {
-            "add" => inp.a + inp.b,
-            "subtract" => inp.a - inp.b,
-            "multiply" => inp.a * inp.b,
-            "divide" => {
+impl TypedToolHandler for Calculator {
+    type Input = CalcInput;
+
+    async fn execute(&self, inp: CalcInput, _ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let (op, result) = match inp.action {
+            Action::Add => ("+", inp.a + inp.b),
+            Action::Subtract => ("-", inp.a - inp.b),
+            Action::Multiply => ("*", inp.a * inp.b),
+            Action::Divide => {
                 if inp.b == 0.0 {
                     return Err(NeboError::Execution("division by zero".into()));
                 }
-                inp.a / inp.b
+                ("/", inp.a / inp.b)
             }
-            other => return Err(NeboError::Execution(format!("unknown action: {other}"))),
         };
 
-        Ok(format!("{} {} {} = {}", inp.a, inp.action, inp.b, result))
+        Ok(format!("{} {} {} = {}", inp.a, op, inp.b, result).into())
     }
 }
 
@@ -0,0 +1,470 @@
+//! Derive macros for the Nebo Rust SDK. Use them through `nebo_sdk`, which
+//! re-exports `NeboSchema` and `nebo_tool`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::parse::Parser;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, GenericParam,
+    ImplItem, ItemImpl, Lit, LitStr, Meta, Token, Type,
+};
+
+/// Derive `nebo_sdk::schema::NeboSchema` from a struct or enum.
+///
+/// - Structs with named fields become `object` schemas. Doc comments become
+///   descriptions; `Option<T>` and `#[serde(default)]` fields, and every field
+///   of a `#[serde(default)]` struct, are optional.
+/// - Enums of unit variants become string enums.
+/// - Enums with `#[serde(tag = "action")]` become STRAP schemas: an `action`
+///   enum plus the union of every variant's parameters.
+///
+/// `#[serde(rename = "..")]`, `rename_all`, `skip`, `skip_deserializing`,
+/// `default` and `flatten` are honored.
+#[proc_macro_derive(NeboSchema, attributes(serde))]
+pub fn derive_nebo_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_schema(&input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Fill in `name` and `description` on an `impl TypedToolHandler` block.
+///
+/// ```ignore
+/// /// Performs arithmetic calculations.
+/// #[nebo_tool(name = "calculator")]
+/// #[async_trait]
+/// impl TypedToolHandler for Calculator {
+///     type Input = CalcInput;
+///     async fn execute(&self, input: CalcInput, ctx: &ToolContext) -> Result<ToolOutput, NeboError> { .. }
+/// }
+/// ```
+///
+/// `name` defaults to the type name in snake_case and `description` to the
+/// doc comment on the impl block.
+#[proc_macro_attribute]
+pub fn nebo_tool(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut item = parse_macro_input!(item as ItemImpl);
+    match expand_tool(args.into(), &mut item) {
+        Ok(()) => quote!(#item).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_schema(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let container = SerdeAttrs::parse(&input.attrs)?;
+    let doc = doc_string(&input.attrs);
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(_) => {
+                let fields = field_list(&data.fields, container.rename_all.as_deref(), container.default)?;
+                quote!(::nebo_sdk::schema::__private::object(#doc, #fields))
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "NeboSchema can only be derived for structs with named fields",
+                ))
+            }
+        },
+        Data::Enum(data) => {
+            let names = data
+                .variants
+                .iter()
+                .map(|v| {
+                    let attrs = SerdeAttrs::parse(&v.attrs)?;
+                    Ok(attrs.rename.unwrap_or_else(|| {
+                        rename_variant(&v.ident.to_string(), container.rename_all.as_deref())
+                    }))
+                })
+                .collect::<syn::Result<Vec<String>>>()?;
+
+            match &container.tag {
+                None => {
+                    if let Some(v) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+                        return Err(syn::Error::new_spanned(
+                            v,
+                            "NeboSchema enums with data need #[serde(tag = \"action\")]",
+                        ));
+                    }
+                    quote!(::nebo_sdk::schema::__private::string_enum(#doc, &[#(#names),*]))
+                }
+                Some(tag) => {
+                    let mut variants = Vec::new();
+                    for (v, name) in data.variants.iter().zip(&names) {
+                        let fields = match &v.fields {
+                            Fields::Unit => quote!(::std::vec::Vec::new()),
+                            Fields::Named(_) => {
+                                let attrs = SerdeAttrs::parse(&v.attrs)?;
+                                field_list(&v.fields, attrs.rename_all.as_deref(), false)?
+                            }
+                            Fields::Unnamed(f) if f.unnamed.len() == 1 => {
+                                let ty = &f.unnamed[0].ty;
+                                quote!(::nebo_sdk::schema::__private::newtype(
+                                    <#ty as ::nebo_sdk::schema::NeboSchema>::nebo_schema()
+                                ))
+                            }
+                            Fields::Unnamed(_) => {
+                                return Err(syn::Error::new_spanned(
+                                    v,
+                                    "tuple variants must wrap exactly one struct",
+                                ))
+                            }
+                        };
+                        variants.push(quote!((#name, #fields)));
+                    }
+                    quote!(::nebo_sdk::schema::__private::tagged(
+                        #doc,
+                        #tag,
+                        ::std::vec![#(#variants),*],
+                    ))
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "NeboSchema cannot be derived for unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in &mut generics.params {
+        if let GenericParam::Type(t) = param {
+            t.bounds.push(syn::parse_quote!(::nebo_sdk::schema::NeboSchema));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::nebo_sdk::schema::NeboSchema for #ident #ty_generics #where_clause {
+            fn nebo_schema() -> ::nebo_sdk::schema::__private::JsonValue {
+                #body
+            }
+        }
+    })
+}
+
+/// Build a `Vec<Field>` expression for a set of named fields. With
+/// `default` (a container-level `#[serde(default)]`) every field is optional.
+fn field_list(fields: &Fields, rename_all: Option<&str>, default: bool) -> syn::Result<TokenStream2> {
+    let mut out = Vec::new();
+    for field in fields {
+        let attrs = SerdeAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let name = attrs.rename.unwrap_or_else(|| {
+            rename_field(ident.to_string().trim_start_matches("r#"), rename_all)
+        });
+        let ty = &field.ty;
+        let doc = doc_string(&field.attrs);
+        let required = !default && !attrs.default && !is_option(ty);
+        let flatten = attrs.flatten;
+
+        let mut schema = quote!(<#ty as ::nebo_sdk::schema::NeboSchema>::nebo_schema());
+        if name == "action" && doc.is_empty() {
+            schema = quote!(::nebo_sdk::schema::__private::action_field(#schema));
+        }
+
+        out.push(quote! {
+            ::nebo_sdk::schema::__private::Field {
+                name: #name,
+                schema: #schema,
+                doc: #doc,
+                required: #required,
+                flatten: #flatten,
+            }
+        });
+    }
+    Ok(quote!(::std::vec![#(#out),*]))
+}
+
+fn expand_tool(args: TokenStream2, item: &mut ItemImpl) -> syn::Result<()> {
+    let mut name: Option<LitStr> = None;
+    let mut description: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("description") {
+            description = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `name` or `description`"))
+        }
+    });
+    parser.parse2(args)?;
+
+    let has_fn = |item: &ItemImpl, f: &str| {
+        item.items
+            .iter()
+            .any(|i| matches!(i, ImplItem::Fn(m) if m.sig.ident == f))
+    };
+
+    if !has_fn(item, "name") {
+        let name = match name {
+            Some(n) => n,
+            None => {
+                let ty = type_ident(&item.self_ty).ok_or_else(|| {
+                    syn::Error::new_spanned(&item.self_ty, "add `name = \"...\"` to #[nebo_tool]")
+                })?;
+                LitStr::new(&to_snake(&ty), Span::call_site())
+            }
+        };
+        item.items.push(syn::parse_quote! {
+            fn name(&self) -> &str {
+                #name
+            }
+        });
+    }
+
+    if !has_fn(item, "description") {
+        let description = match description {
+            Some(d) => d,
+            None => {
+                let doc = doc_string(&item.attrs);
+                if doc.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        &item.self_ty,
+                        "add a doc comment or `description = \"...\"` to #[nebo_tool]",
+                    ));
+                }
+                LitStr::new(&doc, Span::call_site())
+            }
+        };
+        item.items.push(syn::parse_quote! {
+            fn description(&self) -> &str {
+                #description
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    tag: Option<String>,
+    skip: bool,
+    default: bool,
+    flatten: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = SerdeAttrs::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                let key = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+                match key.as_str() {
+                    "rename" | "rename_all" => {
+                        let value = if meta.input.peek(Token![=]) {
+                            Some(meta.value()?.parse::<LitStr>()?.value())
+                        } else {
+                            // rename(deserialize = "..", serialize = "..")
+                            let mut de = None;
+                            meta.parse_nested_meta(|inner| {
+                                let v = inner.value()?.parse::<LitStr>()?.value();
+                                if inner.path.is_ident("deserialize") {
+                                    de = Some(v);
+                                }
+                                Ok(())
+                            })?;
+                            de
+                        };
+                        if key == "rename" {
+                            out.rename = value.or(out.rename.take());
+                        } else {
+                            out.rename_all = value.or(out.rename_all.take());
+                        }
+                    }
+                    "tag" => out.tag = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "skip" | "skip_deserializing" => out.skip = true,
+                    "flatten" => out.flatten = true,
+                    "default" => {
+                        out.default = true;
+                        skip_value(&meta)?;
+                    }
+                    _ => skip_value(&meta)?,
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+/// Consume the value of a serde attribute we don't interpret.
+fn skip_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream2>()?;
+    }
+    Ok(())
+}
+
+/// Join `///` lines into a single-line description.
+fn doc_string(attrs: &[Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if let Meta::NameValue(nv) = &attr.meta {
+            if !nv.path.is_ident("doc") {
+                continue;
+            }
+            if let Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) = &nv.value
+            {
+                let line = s.value().trim().to_string();
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    lines.join(" ")
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn type_ident(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn to_snake(pascal: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in pascal.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Apply a serde `rename_all` rule to a snake_case field name.
+fn rename_field(field: &str, rule: Option<&str>) -> String {
+    let words: Vec<&str> = field.split('_').filter(|w| !w.is_empty()).collect();
+    join_words(&words, rule).unwrap_or_else(|| field.to_string())
+}
+
+/// Apply a serde `rename_all` rule to a PascalCase variant name.
+fn rename_variant(variant: &str, rule: Option<&str>) -> String {
+    let snake = to_snake(variant);
+    let words: Vec<&str> = snake.split('_').collect();
+    join_words(&words, rule).unwrap_or_else(|| variant.to_string())
+}
+
+fn join_words(words: &[&str], rule: Option<&str>) -> Option<String> {
+    let capitalize = |w: &str| {
+        let mut c = w.chars();
+        c.next()
+            .map(|f| f.to_uppercase().chain(c).collect::<String>())
+            .unwrap_or_default()
+    };
+    Some(match rule? {
+        "lowercase" => words.concat().to_lowercase(),
+        "UPPERCASE" => words.concat().to_uppercase(),
+        "snake_case" => words.join("_").to_lowercase(),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-").to_lowercase(),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_uppercase(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_lowercase() } else { capitalize(w) })
+            .collect(),
+        "PascalCase" => words.iter().map(|w| capitalize(w)).collect(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: DeriveInput) -> String {
+        match expand_schema(&input) {
+            Ok(ts) => ts.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn rename_rules() {
+        assert_eq!(rename_field("start_time", Some("camelCase")), "startTime");
+        assert_eq!(rename_field("start_time", Some("kebab-case")), "start-time");
+        assert_eq!(rename_field("start_time", Some("unknown")), "start_time");
+        assert_eq!(rename_variant("VeryHigh", Some("snake_case")), "very_high");
+        assert_eq!(rename_variant("VeryHigh", Some("SCREAMING-KEBAB-CASE")), "VERY-HIGH");
+        assert_eq!(rename_variant("VeryHigh", None), "VeryHigh");
+        assert_eq!(to_snake("HttpClient"), "http_client");
+    }
+
+    #[test]
+    fn serde_attrs() {
+        let attrs: Vec<Attribute> = vec![
+            syn::parse_quote!(#[serde(default = "Default::default", rename(serialize = "x", deserialize = "y"))]),
+            syn::parse_quote!(#[serde(deny_unknown_fields, flatten)]),
+        ];
+        let parsed = SerdeAttrs::parse(&attrs).unwrap();
+        assert!(parsed.default && parsed.flatten && !parsed.skip);
+        assert_eq!(parsed.rename.as_deref(), Some("y"));
+    }
+
+    #[test]
+    fn required_fields_follow_defaults() {
+        let out = expand(syn::parse_quote! {
+            struct S { a: String, b: Option<u32>, #[serde(default)] c: u32 }
+        });
+        let required: Vec<bool> = out.split("required : ").skip(1).map(|s| s.starts_with("true")).collect();
+        assert_eq!(required, [true, false, false]);
+
+        let out = expand(syn::parse_quote! {
+            #[serde(default)]
+            struct S { a: String }
+        });
+        assert!(out.contains("required : false"));
+    }
+
+    #[test]
+    fn unsupported_shapes_are_errors() {
+        let out = expand(syn::parse_quote!(struct S(u32);));
+        assert_eq!(out, "NeboSchema can only be derived for structs with named fields");
+        let out = expand(syn::parse_quote!(enum E { A(u32) }));
+        assert_eq!(out, "NeboSchema enums with data need #[serde(tag = \"action\")]");
+        let out = expand(syn::parse_quote! {
+            #[serde(tag = "action")]
+            enum E { A(u32, u32) }
+        });
+        assert_eq!(out, "tuple variants must wrap exactly one struct");
+    }
+}
@@ -1,23 +1,35 @@
-use std::path::Path;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::UnixListener;
-use tokio::signal;
 use tokio_stream::wrappers::UnixListenerStream;
 use tonic::transport::Server;
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
+use crate::jobs::Jobs;
 use crate::pb;
+use crate::registry::ToolRegistry;
+use crate::settings::{Settings, SettingsValues};
+use crate::shutdown::{self, ShutdownSignal};
+
+/// Default time allowed for in-flight calls to finish after a shutdown signal.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Main entry point for a Nebo app in Rust.
 pub struct NeboApp {
     env: AppEnv,
-    tool: Option<Box<dyn crate::tool::ToolHandler>>,
-    channel: Option<Box<dyn crate::channel::ChannelHandler>>,
-    gateway: Option<Box<dyn crate::gateway::GatewayHandler>>,
-    ui: Option<Box<dyn crate::ui::UiHandler>>,
-    comm: Option<Box<dyn crate::comm::CommHandler>>,
-    schedule: Option<Box<dyn crate::schedule::ScheduleHandler>>,
-    on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    tools: ToolRegistry,
+    channel: Option<Arc<dyn crate::channel::ChannelHandler>>,
+    gateway: Option<Arc<dyn crate::gateway::GatewayHandler>>,
+    ui: Option<Arc<dyn crate::ui::UiHandler>>,
+    comm: Option<Arc<dyn crate::comm::CommHandler>>,
+    schedule: Option<Arc<dyn crate::schedule::ScheduleHandler>>,
+    settings: Settings,
+    drain_timeout: Duration,
+    tool_timeout: Option<Duration>,
 }
 
 impl NeboApp {
@@ -27,16 +39,23 @@ impl NeboApp {
         if env.sock_path.is_empty() {
             return Err(NeboError::NoSockPath);
         }
-        Ok(Self {
+        Ok(Self::with_env(env))
+    }
+
+    /// Create a NeboApp from an explicit environment instead of NEBO_APP_*.
+    pub fn with_env(env: AppEnv) -> Self {
+        Self {
             env,
-            tool: None,
+            tools: ToolRegistry::new(),
             channel: None,
             gateway: None,
             ui: None,
             comm: None,
             schedule: None,
-            on_configure: None,
-        })
+            settings: Settings::new(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            tool_timeout: None,
+        }
     }
 
     /// Returns the app's environment.
@@ -44,48 +63,104 @@ impl NeboApp {
         &self.env
     }
 
+    /// Returns the shared settings store. Clone it into handlers before
+    /// registering them to read or subscribe to settings pushed by Nebo.
+    pub fn settings(&self) -> Settings {
+        self.settings.clone()
+    }
+
     /// Set a callback for settings updates from Nebo.
-    pub fn on_configure<F: Fn(std::collections::HashMap<String, String>) + Send + Sync + 'static>(
-        mut self,
-        f: F,
-    ) -> Self {
-        self.on_configure = Some(Box::new(f));
+    pub fn on_configure<F: Fn(SettingsValues) + Send + Sync + 'static>(self, f: F) -> Self {
+        self.settings.set_on_configure(Box::new(f));
+        self
+    }
+
+    /// Reject settings that do not deserialize into `T`. The error is
+    /// returned to Nebo from the `Configure` RPC and the store is left unchanged.
+    pub fn typed_settings<T: DeserializeOwned + 'static>(self) -> Self {
+        self.settings
+            .add_validator(Box::new(|values| crate::settings::parse::<T>(values).map(|_| ())));
+        self
+    }
+
+    /// Add a custom validation step for settings pushed by Nebo.
+    pub fn validate_settings<F>(self, f: F) -> Self
+    where
+        F: Fn(&SettingsValues) -> Result<(), NeboError> + Send + Sync + 'static,
+    {
+        self.settings.add_validator(Box::new(f));
         self
     }
 
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_sock_path(&mut self, path: String) {
+        self.env.sock_path = path;
+    }
+
+    /// How long in-flight calls may run after SIGTERM/SIGINT before the
+    /// server stops waiting for them. Defaults to 10 seconds.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Timeout for tools that do not set their own via
+    /// [`ToolHandler::timeout`](crate::tool::ToolHandler::timeout). Unset by default.
+    pub fn tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Register a tool. May be called repeatedly to host several tools;
+    /// names must be unique or `run` fails.
     pub fn register_tool(mut self, h: impl crate::tool::ToolHandler) -> Self {
-        self.tool = Some(Box::new(h));
+        self.tools.add(h);
+        self
+    }
+
+    /// Register every tool in a registry.
+    pub fn register_tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools.extend(tools);
         self
     }
 
     pub fn register_channel(mut self, h: impl crate::channel::ChannelHandler) -> Self {
-        self.channel = Some(Box::new(h));
+        self.channel = Some(Arc::new(h));
         self
     }
 
     pub fn register_gateway(mut self, h: impl crate::gateway::GatewayHandler) -> Self {
-        self.gateway = Some(Box::new(h));
+        self.gateway = Some(Arc::new(h));
         self
     }
 
     pub fn register_ui(mut self, h: impl crate::ui::UiHandler) -> Self {
-        self.ui = Some(Box::new(h));
+        self.ui = Some(Arc::new(h));
         self
     }
 
     pub fn register_comm(mut self, h: impl crate::comm::CommHandler) -> Self {
-        self.comm = Some(Box::new(h));
+        self.comm = Some(Arc::new(h));
         self
     }
 
     pub fn register_schedule(mut self, h: impl crate::schedule::ScheduleHandler) -> Self {
-        self.schedule = Some(Box::new(h));
+        self.schedule = Some(Arc::new(h));
         self
     }
 
     /// Start the gRPC server on the Unix socket and block until SIGTERM/SIGINT.
     pub async fn run(self) -> Result<(), NeboError> {
-        if self.tool.is_none()
+        self.run_until(shutdown::os_signal()).await
+    }
+
+    /// Start the gRPC server and block until `signal` resolves.
+    ///
+    /// On shutdown, open `Receive`/`Stream`/`Triggers` streams are closed,
+    /// in-flight calls get up to the drain timeout to finish, every handler's
+    /// `on_shutdown` hook runs, and the socket file is removed.
+    pub async fn run_until<F: Future<Output = ()>>(self, signal: F) -> Result<(), NeboError> {
+        if self.tools.is_empty()
             && self.channel.is_none()
             && self.gateway.is_none()
             && self.ui.is_none()
@@ -94,62 +169,81 @@ impl NeboApp {
         {
             return Err(NeboError::NoHandlers);
         }
+        self.tools.check()?;
 
         // Remove stale socket
         let sock_path = &self.env.sock_path;
         let _ = std::fs::remove_file(sock_path);
 
         let uds = UnixListener::bind(sock_path)?;
+        let _socket = SocketGuard(PathBuf::from(sock_path));
         let uds_stream = UnixListenerStream::new(uds);
 
+        self.start_handlers().await?;
+
+        let (shutdown_tx, shutdown) = ShutdownSignal::new();
         let mut builder = Server::builder();
 
+        let jobs = if self.tools.is_empty() {
+            Jobs::default()
+        } else {
+            Jobs::open(&self.env)
+        };
+
         // Register each capability's gRPC service
-        let mut router = builder.add_optional_service(self.tool.map(|h| {
+        let tools = (!self.tools.is_empty()).then(|| self.tools.clone());
+        let mut router = builder.add_optional_service(tools.map(|tools| {
             pb::tool_service_server::ToolServiceServer::new(crate::tool::ToolBridge {
-                handler: h,
-                on_configure: None,
+                tools,
+                settings: self.settings.clone(),
                 env: self.env.clone(),
+                plans: Default::default(),
+                default_timeout: self.tool_timeout,
+                jobs,
             })
         }));
 
-        router = router.add_optional_service(self.channel.map(|h| {
+        router = router.add_optional_service(self.channel.clone().map(|h| {
             pb::channel_service_server::ChannelServiceServer::new(crate::channel::ChannelBridge {
                 handler: h,
-                on_configure: None,
+                settings: self.settings.clone(),
+                shutdown: shutdown.clone(),
                 env: self.env.clone(),
             })
         }));
 
-        router = router.add_optional_service(self.gateway.map(|h| {
+        router = router.add_optional_service(self.gateway.clone().map(|h| {
             pb::gateway_service_server::GatewayServiceServer::new(crate::gateway::GatewayBridge {
                 handler: h,
-                on_configure: None,
+                settings: self.settings.clone(),
+                shutdown: shutdown.clone(),
                 env: self.env.clone(),
             })
         }));
 
-        router = router.add_optional_service(self.ui.map(|h| {
+        router = router.add_optional_service(self.ui.clone().map(|h| {
             pb::ui_service_server::UiServiceServer::new(crate::ui::UiBridge {
                 handler: h,
-                on_configure: None,
+                settings: self.settings.clone(),
                 env: self.env.clone(),
             })
         }));
 
-        router = router.add_optional_service(self.comm.map(|h| {
+        router = router.add_optional_service(self.comm.clone().map(|h| {
             pb::comm_service_server::CommServiceServer::new(crate::comm::CommBridge {
                 handler: h,
-                on_configure: None,
+                settings: self.settings.clone(),
+                shutdown: shutdown.clone(),
                 env: self.env.clone(),
             })
         }));
 
-        router = router.add_optional_service(self.schedule.map(|h| {
+        router = router.add_optional_service(self.schedule.clone().map(|h| {
             pb::schedule_service_server::ScheduleServiceServer::new(
                 crate::schedule::ScheduleBridge {
                     handler: h,
-                    on_configure: None,
+                    settings: self.settings.clone(),
+                    shutdown: shutdown.clone(),
                     env: self.env.clone(),
                 },
             )
@@ -161,12 +255,140 @@ impl NeboApp {
             Path::new(sock_path).display()
         );
 
-        router
-            .serve_with_incoming_shutdown(uds_stream, async {
-                let _ = signal::ctrl_c().await;
-            })
-            .await?;
+        let serve = router.serve_with_incoming_shutdown(uds_stream, async move {
+            signal.await;
+            let _ = shutdown_tx.send(true);
+        });
+        tokio::pin!(serve);
+
+        let mut stopping = shutdown.clone();
+        let result = tokio::select! {
+            res = &mut serve => res,
+            _ = stopping.wait() => {
+                match tokio::time::timeout(self.drain_timeout, &mut serve).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        eprintln!(
+                            "[{}] drain timeout of {:?} elapsed, abandoning in-flight calls",
+                            self.env.name, self.drain_timeout
+                        );
+                        Ok(())
+                    }
+                }
+            }
+        };
+
+        self.stop_handlers().await;
+        result?;
+        Ok(())
+    }
+
+    /// Serve the registered tools as an MCP server over stdin/stdout instead
+    /// of the Nebo socket, until stdin closes or SIGTERM/SIGINT.
+    ///
+    /// Only tools are served. Since no socket is needed, build the app with
+    /// `NeboApp::with_env(AppEnv::load())`. Stdout carries the protocol, so
+    /// tools must log to stderr.
+    pub async fn run_mcp_stdio(self) -> Result<(), NeboError> {
+        self.run_mcp(tokio::io::stdin(), tokio::io::stdout(), shutdown::os_signal())
+            .await
+    }
+
+    /// Serve MCP over `input` and `output` until `input` closes or `signal`
+    /// resolves.
+    pub(crate) async fn run_mcp<R, W, F>(
+        self,
+        input: R,
+        output: W,
+        signal: F,
+    ) -> Result<(), NeboError>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        W: tokio::io::AsyncWrite + Unpin + Send + 'static,
+        F: Future<Output = ()>,
+    {
+        if self.tools.is_empty() {
+            return Err(NeboError::NoHandlers);
+        }
+        self.tools.check()?;
+        for t in self.tools.iter() {
+            t.on_start().await?;
+        }
 
+        let bridge = crate::tool::ToolBridge {
+            tools: self.tools.clone(),
+            settings: self.settings.clone(),
+            env: self.env.clone(),
+            plans: Default::default(),
+            default_timeout: self.tool_timeout,
+            jobs: Jobs::open(&self.env),
+        };
+        let result =
+            crate::mcp::server::serve(bridge, input, output, signal, self.drain_timeout).await;
+
+        for t in self.tools.iter() {
+            if let Err(e) = t.on_shutdown().await {
+                eprintln!("[{}] shutdown hook failed: {}", self.env.name, e);
+            }
+        }
+        result
+    }
+
+    async fn start_handlers(&self) -> Result<(), NeboError> {
+        for t in self.tools.iter() {
+            t.on_start().await?;
+        }
+        if let Some(h) = &self.channel {
+            h.on_start().await?;
+        }
+        if let Some(h) = &self.gateway {
+            h.on_start().await?;
+        }
+        if let Some(h) = &self.ui {
+            h.on_start().await?;
+        }
+        if let Some(h) = &self.comm {
+            h.on_start().await?;
+        }
+        if let Some(h) = &self.schedule {
+            h.on_start().await?;
+        }
         Ok(())
     }
+
+    /// Run every `on_shutdown` hook, logging failures so one handler cannot
+    /// prevent the others from cleaning up.
+    async fn stop_handlers(&self) {
+        let mut results = Vec::new();
+        for t in self.tools.iter() {
+            results.push(t.on_shutdown().await);
+        }
+        if let Some(h) = &self.channel {
+            results.push(h.on_shutdown().await);
+        }
+        if let Some(h) = &self.gateway {
+            results.push(h.on_shutdown().await);
+        }
+        if let Some(h) = &self.ui {
+            results.push(h.on_shutdown().await);
+        }
+        if let Some(h) = &self.comm {
+            results.push(h.on_shutdown().await);
+        }
+        if let Some(h) = &self.schedule {
+            results.push(h.on_shutdown().await);
+        }
+        for e in results.into_iter().filter_map(Result::err) {
+            eprintln!("[{}] shutdown hook failed: {}", self.env.name, e);
+        }
+    }
+}
+
+/// Removes the Unix socket file when the server exits, on any path.
+struct SocketGuard(PathBuf);
+
+impl Drop for SocketGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
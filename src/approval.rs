@@ -0,0 +1,76 @@
+use serde_json::Value;
+
+use crate::output::ToolOutput;
+use crate::pb;
+
+/// Whether one tool call needs user confirmation, and what to show the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Approval {
+    pub required: bool,
+    /// Human-readable description of what the call will do.
+    pub prompt: String,
+    /// Files, URLs or accounts the call touches.
+    pub resources: Vec<String>,
+}
+
+impl Approval {
+    pub fn not_required() -> Self {
+        Self::default()
+    }
+
+    pub fn required(prompt: impl Into<String>) -> Self {
+        Self {
+            required: true,
+            prompt: prompt.into(),
+            resources: Vec::new(),
+        }
+    }
+
+    pub fn with_resource(mut self, resource: impl Into<String>) -> Self {
+        self.resources.push(resource.into());
+        self
+    }
+}
+
+impl From<bool> for Approval {
+    fn from(required: bool) -> Self {
+        Self {
+            required,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<Approval> for pb::ApprovalDecision {
+    fn from(a: Approval) -> Self {
+        Self {
+            requires_approval: a.required,
+            prompt: a.prompt,
+            resources: a.resources,
+        }
+    }
+}
+
+/// What a tool call would do, computed without side effects.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preview {
+    /// Shown to the user in the approval dialog: a diff, a file list, a draft.
+    pub output: ToolOutput,
+    /// Handed back to `execute` as [`ToolContext::approved_plan`](crate::ToolContext::approved_plan)
+    /// once the user approves, so it runs exactly what was previewed.
+    pub plan: Value,
+}
+
+impl Preview {
+    pub fn new(output: impl Into<ToolOutput>) -> Self {
+        Self {
+            output: output.into(),
+            plan: Value::Null,
+        }
+    }
+
+    pub fn with_plan(mut self, plan: Value) -> Self {
+        self.plan = plan;
+        self
+    }
+}
@@ -0,0 +1,877 @@
+//! Blocking variants of the handler traits, for synchronous code.
+//!
+//! Handlers that call into a blocking C library, `std::process::Command` or
+//! long CPU-bound work would stall every other RPC if they ran on the async
+//! runtime. Implement the blocking trait instead and register the handler
+//! wrapped in [`Blocking`]: each call then runs on tokio's blocking thread
+//! pool, with at most [`BlockingPool::size`] calls running at once. Nothing
+//! is moved off the runtime implicitly: async handlers, and blocking
+//! handlers registered without the [`Blocking`] wrapper, still run inline.
+//!
+//! ```no_run
+//! use nebo_sdk::blocking::{Blocking, BlockingPool, BlockingToolHandler};
+//! use nebo_sdk::{NeboApp, NeboError, ToolContext, ToolOutput};
+//! use serde_json::{json, Value};
+//!
+//! struct Convert;
+//!
+//! impl BlockingToolHandler for Convert {
+//!     fn name(&self) -> &str {
+//!         "convert"
+//!     }
+//!     fn description(&self) -> &str {
+//!         "Convert a document to PDF."
+//!     }
+//!     fn schema(&self) -> Value {
+//!         json!({"type": "object", "properties": {"path": {"type": "string"}}})
+//!     }
+//!     fn execute(&self, input: Value, _ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+//!         let path = input["path"].as_str().unwrap_or_default();
+//!         let status = std::process::Command::new("soffice")
+//!             .args(["--headless", "--convert-to", "pdf", path])
+//!             .status()?;
+//!         Ok(format!("soffice exited with {status}").into())
+//!     }
+//! }
+//!
+//! # async fn run() -> Result<(), NeboError> {
+//! let pool = BlockingPool::new(2);
+//! NeboApp::new()?.register_tool(Blocking::new(Convert).pool(pool)).run().await
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::approval::{Approval, Preview};
+use crate::channel::{ChannelEnvelope, ChannelHandler};
+use crate::comm::{CommHandler, CommMessage};
+use crate::context::ToolContext;
+use crate::error::NeboError;
+use crate::gateway::{GatewayEvent, GatewayHandler, GatewayRequest};
+use crate::output::ToolOutput;
+use crate::schedule::{
+    CreateScheduleRequest, Schedule, ScheduleHandler, ScheduleHistoryEntry, ScheduleTrigger,
+    UpdateScheduleRequest,
+};
+use crate::tool::ToolHandler;
+use crate::ui::{HttpRequest, HttpResponse, UiHandler};
+
+/// Bounds how many blocking calls run at once.
+///
+/// Calls beyond the limit wait for a free slot without holding a thread. A
+/// slot stays taken until the call returns, even if the RPC that started it
+/// was abandoned (e.g. by a tool timeout), since a blocking call cannot be
+/// interrupted; long-running tools should check `ctx.cancel.is_cancelled()`.
+/// Clones share the same slots, so one pool can bound several handlers.
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    slots: Arc<Semaphore>,
+    size: usize,
+}
+
+impl BlockingPool {
+    /// A pool that runs at most `size` calls at once (at least one).
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            slots: Arc::new(Semaphore::new(size)),
+            size,
+        }
+    }
+
+    /// The pool shared by every [`Blocking`] handler without its own pool,
+    /// with one slot per available CPU.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<BlockingPool> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+                BlockingPool::new(cpus)
+            })
+            .clone()
+    }
+
+    /// Maximum number of calls running at once.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Run `f` on the blocking thread pool once a slot is free.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, NeboError>
+    where
+        F: FnOnce() -> Result<T, NeboError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| NeboError::Execution("blocking pool is closed".into()))?;
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        });
+        match task.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => Err(NeboError::Execution("blocking handler panicked".into())),
+            Err(e) => Err(NeboError::Execution(e.to_string())),
+        }
+    }
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        Self::shared()
+    }
+}
+
+/// Adapts a blocking handler to the async handler trait of its capability,
+/// running every call on a [`BlockingPool`].
+pub struct Blocking<H> {
+    handler: Arc<H>,
+    pool: BlockingPool,
+}
+
+impl<H: Send + Sync + 'static> Blocking<H> {
+    /// Wrap `handler`, running its calls on [`BlockingPool::shared`].
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            pool: BlockingPool::shared(),
+        }
+    }
+
+    /// Run calls on `pool` instead of the shared pool.
+    pub fn pool(mut self, pool: BlockingPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    async fn call<T, F>(&self, f: F) -> Result<T, NeboError>
+    where
+        F: FnOnce(&H) -> Result<T, NeboError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let handler = self.handler.clone();
+        self.pool.run(move || f(&handler)).await
+    }
+}
+
+/// Blocking counterpart of [`ToolHandler`]; register it wrapped in [`Blocking`].
+///
+/// `execute`, `approval`, `preview` and the lifecycle hooks run on the
+/// blocking pool. The context works as usual: progress can be reported from
+/// the blocking thread, and `ctx.cancel` tells a long call to give up.
+pub trait BlockingToolHandler: Send + Sync + 'static {
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn schema(&self) -> Value;
+    fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError>;
+    fn requires_approval(&self) -> bool {
+        false
+    }
+    fn approval(&self, input: &Value, ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let _ = (input, ctx);
+        Ok(BlockingToolHandler::requires_approval(self).into())
+    }
+    fn preview(&self, input: &Value, ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let _ = (input, ctx);
+        Ok(None)
+    }
+    fn reports_progress(&self) -> bool {
+        false
+    }
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingToolHandler> ToolHandler for Blocking<H> {
+    fn name(&self) -> &str {
+        self.handler.name()
+    }
+    fn description(&self) -> &str {
+        self.handler.description()
+    }
+    fn schema(&self) -> Value {
+        self.handler.schema()
+    }
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let ctx = ctx.clone();
+        self.call(move |h| h.execute(input, &ctx)).await
+    }
+    fn requires_approval(&self) -> bool {
+        self.handler.requires_approval()
+    }
+    async fn approval(&self, input: &Value, ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let (input, ctx) = (input.clone(), ctx.clone());
+        self.call(move |h| h.approval(&input, &ctx)).await
+    }
+    async fn preview(&self, input: &Value, ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let (input, ctx) = (input.clone(), ctx.clone());
+        self.call(move |h| h.preview(&input, &ctx)).await
+    }
+    fn reports_progress(&self) -> bool {
+        self.handler.reports_progress()
+    }
+    fn timeout(&self) -> Option<Duration> {
+        self.handler.timeout()
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingToolHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingToolHandler::on_shutdown(h)).await
+    }
+}
+
+/// Blocking counterpart of [`ChannelHandler`]; register it wrapped in [`Blocking`].
+///
+/// `receive` hands back the receiving end of a channel; feed it from a
+/// thread of your own with [`mpsc::Sender::blocking_send`].
+pub trait BlockingChannelHandler: Send + Sync + 'static {
+    fn id(&self) -> &str;
+    fn connect(&self, config: HashMap<String, String>) -> Result<(), NeboError>;
+    fn disconnect(&self) -> Result<(), NeboError>;
+    /// Send a message. Returns the platform-assigned message ID.
+    fn send(&self, env: ChannelEnvelope) -> Result<String, NeboError>;
+    fn receive(&self) -> Result<mpsc::Receiver<ChannelEnvelope>, NeboError>;
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingChannelHandler> ChannelHandler for Blocking<H> {
+    fn id(&self) -> &str {
+        self.handler.id()
+    }
+    async fn connect(&self, config: HashMap<String, String>) -> Result<(), NeboError> {
+        self.call(move |h| h.connect(config)).await
+    }
+    async fn disconnect(&self) -> Result<(), NeboError> {
+        self.call(|h| h.disconnect()).await
+    }
+    async fn send(&self, env: ChannelEnvelope) -> Result<String, NeboError> {
+        self.call(move |h| h.send(env)).await
+    }
+    async fn receive(&self) -> Result<mpsc::Receiver<ChannelEnvelope>, NeboError> {
+        self.call(|h| h.receive()).await
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingChannelHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingChannelHandler::on_shutdown(h)).await
+    }
+}
+
+/// Blocking counterpart of [`GatewayHandler`]; register it wrapped in [`Blocking`].
+///
+/// `stream` should return quickly and produce events from a thread of its
+/// own with [`mpsc::Sender::blocking_send`].
+pub trait BlockingGatewayHandler: Send + Sync + 'static {
+    fn stream(&self, req: GatewayRequest) -> Result<mpsc::Receiver<GatewayEvent>, NeboError>;
+    fn cancel(&self, request_id: &str) -> Result<(), NeboError>;
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingGatewayHandler> GatewayHandler for Blocking<H> {
+    async fn stream(&self, req: GatewayRequest) -> Result<mpsc::Receiver<GatewayEvent>, NeboError> {
+        self.call(move |h| h.stream(req)).await
+    }
+    async fn cancel(&self, request_id: &str) -> Result<(), NeboError> {
+        let request_id = request_id.to_string();
+        self.call(move |h| h.cancel(&request_id)).await
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingGatewayHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingGatewayHandler::on_shutdown(h)).await
+    }
+}
+
+/// Blocking counterpart of [`UiHandler`]; register it wrapped in [`Blocking`].
+pub trait BlockingUiHandler: Send + Sync + 'static {
+    fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, NeboError>;
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingUiHandler> UiHandler for Blocking<H> {
+    async fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, NeboError> {
+        self.call(move |h| h.handle_request(req)).await
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingUiHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingUiHandler::on_shutdown(h)).await
+    }
+}
+
+/// Blocking counterpart of [`CommHandler`]; register it wrapped in [`Blocking`].
+///
+/// Every method but `name` and `version` runs on the blocking pool; an
+/// `is_connected` that panics reports the connection as down. `receive` hands back the receiving end of a channel; feed it from a
+/// thread of your own with [`mpsc::Sender::blocking_send`].
+pub trait BlockingCommHandler: Send + Sync + 'static {
+    fn name(&self) -> &str;
+    fn version(&self) -> &str;
+    fn connect(&self, config: HashMap<String, String>) -> Result<(), NeboError>;
+    fn disconnect(&self) -> Result<(), NeboError>;
+    fn is_connected(&self) -> bool;
+    fn send(&self, msg: CommMessage) -> Result<(), NeboError>;
+    fn subscribe(&self, topic: &str) -> Result<(), NeboError>;
+    fn unsubscribe(&self, topic: &str) -> Result<(), NeboError>;
+    fn register(&self, agent_id: &str, capabilities: &[String]) -> Result<(), NeboError>;
+    fn deregister(&self) -> Result<(), NeboError>;
+    fn receive(&self) -> Result<mpsc::Receiver<CommMessage>, NeboError>;
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingCommHandler> CommHandler for Blocking<H> {
+    fn name(&self) -> &str {
+        self.handler.name()
+    }
+    fn version(&self) -> &str {
+        self.handler.version()
+    }
+    async fn connect(&self, config: HashMap<String, String>) -> Result<(), NeboError> {
+        self.call(move |h| h.connect(config)).await
+    }
+    async fn disconnect(&self) -> Result<(), NeboError> {
+        self.call(|h| h.disconnect()).await
+    }
+    async fn is_connected(&self) -> bool {
+        self.call(|h| Ok(h.is_connected())).await.unwrap_or(false)
+    }
+    async fn send(&self, msg: CommMessage) -> Result<(), NeboError> {
+        self.call(move |h| h.send(msg)).await
+    }
+    async fn subscribe(&self, topic: &str) -> Result<(), NeboError> {
+        let topic = topic.to_string();
+        self.call(move |h| h.subscribe(&topic)).await
+    }
+    async fn unsubscribe(&self, topic: &str) -> Result<(), NeboError> {
+        let topic = topic.to_string();
+        self.call(move |h| h.unsubscribe(&topic)).await
+    }
+    async fn register(&self, agent_id: &str, capabilities: &[String]) -> Result<(), NeboError> {
+        let (agent_id, capabilities) = (agent_id.to_string(), capabilities.to_vec());
+        self.call(move |h| h.register(&agent_id, &capabilities)).await
+    }
+    async fn deregister(&self) -> Result<(), NeboError> {
+        self.call(|h| h.deregister()).await
+    }
+    async fn receive(&self) -> Result<mpsc::Receiver<CommMessage>, NeboError> {
+        self.call(|h| h.receive()).await
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingCommHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingCommHandler::on_shutdown(h)).await
+    }
+}
+
+/// Blocking counterpart of [`ScheduleHandler`]; register it wrapped in [`Blocking`].
+pub trait BlockingScheduleHandler: Send + Sync + 'static {
+    fn create(&self, req: CreateScheduleRequest) -> Result<Schedule, NeboError>;
+    fn get(&self, name: &str) -> Result<Schedule, NeboError>;
+    fn list(&self, limit: i32, offset: i32, enabled_only: bool) -> Result<(Vec<Schedule>, i64), NeboError>;
+    fn update(&self, req: UpdateScheduleRequest) -> Result<Schedule, NeboError>;
+    fn delete(&self, name: &str) -> Result<(), NeboError>;
+    fn enable(&self, name: &str) -> Result<Schedule, NeboError>;
+    fn disable(&self, name: &str) -> Result<Schedule, NeboError>;
+    fn trigger(&self, name: &str) -> Result<(bool, String), NeboError>;
+    fn history(&self, name: &str, limit: i32, offset: i32) -> Result<(Vec<ScheduleHistoryEntry>, i64), NeboError>;
+    fn triggers(&self) -> Result<mpsc::Receiver<ScheduleTrigger>, NeboError>;
+    fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<H: BlockingScheduleHandler> ScheduleHandler for Blocking<H> {
+    async fn create(&self, req: CreateScheduleRequest) -> Result<Schedule, NeboError> {
+        self.call(move |h| h.create(req)).await
+    }
+    async fn get(&self, name: &str) -> Result<Schedule, NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.get(&name)).await
+    }
+    async fn list(&self, limit: i32, offset: i32, enabled_only: bool) -> Result<(Vec<Schedule>, i64), NeboError> {
+        self.call(move |h| h.list(limit, offset, enabled_only)).await
+    }
+    async fn update(&self, req: UpdateScheduleRequest) -> Result<Schedule, NeboError> {
+        self.call(move |h| h.update(req)).await
+    }
+    async fn delete(&self, name: &str) -> Result<(), NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.delete(&name)).await
+    }
+    async fn enable(&self, name: &str) -> Result<Schedule, NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.enable(&name)).await
+    }
+    async fn disable(&self, name: &str) -> Result<Schedule, NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.disable(&name)).await
+    }
+    async fn trigger(&self, name: &str) -> Result<(bool, String), NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.trigger(&name)).await
+    }
+    async fn history(&self, name: &str, limit: i32, offset: i32) -> Result<(Vec<ScheduleHistoryEntry>, i64), NeboError> {
+        let name = name.to_string();
+        self.call(move |h| h.history(&name, limit, offset)).await
+    }
+    async fn triggers(&self) -> Result<mpsc::Receiver<ScheduleTrigger>, NeboError> {
+        self.call(|h| h.triggers()).await
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingScheduleHandler::on_start(h)).await
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.call(|h| BlockingScheduleHandler::on_shutdown(h)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+
+    /// Implements every blocking trait, recording each call and checking
+    /// that none of them runs on the test's runtime thread.
+    struct Probe {
+        runtime: ThreadId,
+        panics: bool,
+        calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl Probe {
+        fn new() -> Self {
+            Self { runtime: thread::current().id(), panics: false, calls: Mutex::default() }
+        }
+
+        fn panicking() -> Self {
+            Self { panics: true, ..Self::new() }
+        }
+
+        fn hit(&self, call: &'static str) -> Result<(), NeboError> {
+            assert!(!self.panics, "{call} blew up");
+            assert_ne!(thread::current().id(), self.runtime, "{call} ran on the runtime");
+            self.calls.lock().unwrap().push(call);
+            Ok(())
+        }
+    }
+
+    fn calls(blocking: &Blocking<Probe>) -> Vec<&'static str> {
+        blocking.handler.calls.lock().unwrap().clone()
+    }
+
+    impl BlockingToolHandler for Probe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+        fn description(&self) -> &str {
+            "Records its calls."
+        }
+        fn schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+        fn execute(&self, _input: Value, _ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+            self.hit("execute")?;
+            Ok("done".into())
+        }
+        fn approval(&self, _input: &Value, _ctx: &ToolContext) -> Result<Approval, NeboError> {
+            self.hit("approval")?;
+            Ok(Approval::required("run the probe"))
+        }
+        fn preview(&self, _input: &Value, _ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+            self.hit("preview")?;
+            Ok(None)
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    impl BlockingChannelHandler for Probe {
+        fn id(&self) -> &str {
+            "probe"
+        }
+        fn connect(&self, _config: HashMap<String, String>) -> Result<(), NeboError> {
+            self.hit("connect")
+        }
+        fn disconnect(&self) -> Result<(), NeboError> {
+            self.hit("disconnect")
+        }
+        fn send(&self, env: ChannelEnvelope) -> Result<String, NeboError> {
+            self.hit("send")?;
+            Ok(format!("sent-{}", env.text))
+        }
+        fn receive(&self) -> Result<mpsc::Receiver<ChannelEnvelope>, NeboError> {
+            self.hit("receive")?;
+            Ok(mpsc::channel(1).1)
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    impl BlockingGatewayHandler for Probe {
+        fn stream(&self, _req: GatewayRequest) -> Result<mpsc::Receiver<GatewayEvent>, NeboError> {
+            self.hit("stream")?;
+            Ok(mpsc::channel(1).1)
+        }
+        fn cancel(&self, _request_id: &str) -> Result<(), NeboError> {
+            self.hit("cancel")
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    impl BlockingUiHandler for Probe {
+        fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, NeboError> {
+            self.hit("handle_request")?;
+            Ok(HttpResponse { status_code: 200, headers: HashMap::new(), body: req.body })
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    impl BlockingCommHandler for Probe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+        fn connect(&self, _config: HashMap<String, String>) -> Result<(), NeboError> {
+            self.hit("connect")
+        }
+        fn disconnect(&self) -> Result<(), NeboError> {
+            self.hit("disconnect")
+        }
+        fn is_connected(&self) -> bool {
+            self.hit("is_connected").is_ok()
+        }
+        fn send(&self, _msg: CommMessage) -> Result<(), NeboError> {
+            self.hit("send")
+        }
+        fn subscribe(&self, _topic: &str) -> Result<(), NeboError> {
+            self.hit("subscribe")
+        }
+        fn unsubscribe(&self, _topic: &str) -> Result<(), NeboError> {
+            self.hit("unsubscribe")
+        }
+        fn register(&self, _agent_id: &str, _capabilities: &[String]) -> Result<(), NeboError> {
+            self.hit("register")
+        }
+        fn deregister(&self) -> Result<(), NeboError> {
+            self.hit("deregister")
+        }
+        fn receive(&self) -> Result<mpsc::Receiver<CommMessage>, NeboError> {
+            self.hit("receive")?;
+            Ok(mpsc::channel(1).1)
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    impl BlockingScheduleHandler for Probe {
+        fn create(&self, req: CreateScheduleRequest) -> Result<Schedule, NeboError> {
+            self.hit("create")?;
+            Ok(Schedule { name: req.name, ..Default::default() })
+        }
+        fn get(&self, name: &str) -> Result<Schedule, NeboError> {
+            self.hit("get")?;
+            Ok(Schedule { name: name.into(), ..Default::default() })
+        }
+        fn list(&self, _limit: i32, _offset: i32, _enabled_only: bool) -> Result<(Vec<Schedule>, i64), NeboError> {
+            self.hit("list")?;
+            Ok((Vec::new(), 0))
+        }
+        fn update(&self, req: UpdateScheduleRequest) -> Result<Schedule, NeboError> {
+            self.hit("update")?;
+            Ok(Schedule { name: req.name, ..Default::default() })
+        }
+        fn delete(&self, _name: &str) -> Result<(), NeboError> {
+            self.hit("delete")
+        }
+        fn enable(&self, name: &str) -> Result<Schedule, NeboError> {
+            self.hit("enable")?;
+            Ok(Schedule { name: name.into(), enabled: true, ..Default::default() })
+        }
+        fn disable(&self, name: &str) -> Result<Schedule, NeboError> {
+            self.hit("disable")?;
+            Ok(Schedule { name: name.into(), ..Default::default() })
+        }
+        fn trigger(&self, name: &str) -> Result<(bool, String), NeboError> {
+            self.hit("trigger")?;
+            Ok((true, format!("triggered {name}")))
+        }
+        fn history(&self, _name: &str, _limit: i32, _offset: i32) -> Result<(Vec<ScheduleHistoryEntry>, i64), NeboError> {
+            self.hit("history")?;
+            Ok((Vec::new(), 0))
+        }
+        fn triggers(&self) -> Result<mpsc::Receiver<ScheduleTrigger>, NeboError> {
+            self.hit("triggers")?;
+            Ok(mpsc::channel(1).1)
+        }
+        fn on_start(&self) -> Result<(), NeboError> {
+            self.hit("on_start")
+        }
+        fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hit("on_shutdown")
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_calls_run_on_the_pool() {
+        let tool = Blocking::new(Probe::new());
+        let ctx = ToolContext::default();
+        assert_eq!(ToolHandler::name(&tool), "probe");
+        assert_eq!(ToolHandler::schema(&tool), json!({"type": "object"}));
+        ToolHandler::on_start(&tool).await.unwrap();
+        let output = ToolHandler::execute(&tool, json!({}), &ctx).await.unwrap();
+        assert_eq!(output.to_text(), "done");
+        let approval = ToolHandler::approval(&tool, &json!({}), &ctx).await.unwrap();
+        assert_eq!(approval, Approval::required("run the probe"));
+        assert!(ToolHandler::preview(&tool, &json!({}), &ctx).await.unwrap().is_none());
+        ToolHandler::on_shutdown(&tool).await.unwrap();
+        assert_eq!(
+            calls(&tool),
+            ["on_start", "execute", "approval", "preview", "on_shutdown"]
+        );
+    }
+
+    #[tokio::test]
+    async fn channel_calls_run_on_the_pool() {
+        let channel = Blocking::new(Probe::new());
+        assert_eq!(ChannelHandler::id(&channel), "probe");
+        ChannelHandler::on_start(&channel).await.unwrap();
+        ChannelHandler::connect(&channel, HashMap::new()).await.unwrap();
+        let env = ChannelEnvelope { text: "hi".into(), ..Default::default() };
+        assert_eq!(ChannelHandler::send(&channel, env).await.unwrap(), "sent-hi");
+        ChannelHandler::receive(&channel).await.unwrap();
+        ChannelHandler::disconnect(&channel).await.unwrap();
+        ChannelHandler::on_shutdown(&channel).await.unwrap();
+        assert_eq!(
+            calls(&channel),
+            ["on_start", "connect", "send", "receive", "disconnect", "on_shutdown"]
+        );
+    }
+
+    #[tokio::test]
+    async fn gateway_calls_run_on_the_pool() {
+        let gateway = Blocking::new(Probe::new());
+        let req = GatewayRequest {
+            request_id: "req-1".into(),
+            messages: Vec::new(),
+            tools: Vec::new(),
+            max_tokens: 0,
+            temperature: 0.0,
+            system: String::new(),
+            user_id: String::new(),
+            user_plan: String::new(),
+            user_token: String::new(),
+        };
+        GatewayHandler::on_start(&gateway).await.unwrap();
+        GatewayHandler::stream(&gateway, req).await.unwrap();
+        GatewayHandler::cancel(&gateway, "req-1").await.unwrap();
+        GatewayHandler::on_shutdown(&gateway).await.unwrap();
+        assert_eq!(calls(&gateway), ["on_start", "stream", "cancel", "on_shutdown"]);
+    }
+
+    #[tokio::test]
+    async fn ui_calls_run_on_the_pool() {
+        let ui = Blocking::new(Probe::new());
+        let req = HttpRequest {
+            method: "POST".into(),
+            path: "/echo".into(),
+            query: String::new(),
+            headers: HashMap::new(),
+            body: b"ping".to_vec(),
+        };
+        UiHandler::on_start(&ui).await.unwrap();
+        let resp = UiHandler::handle_request(&ui, req).await.unwrap();
+        assert_eq!((resp.status_code, resp.body), (200, b"ping".to_vec()));
+        UiHandler::on_shutdown(&ui).await.unwrap();
+        assert_eq!(calls(&ui), ["on_start", "handle_request", "on_shutdown"]);
+    }
+
+    #[tokio::test]
+    async fn comm_calls_run_on_the_pool() {
+        let comm = Blocking::new(Probe::new());
+        let msg = CommMessage {
+            id: "m1".into(),
+            from: "a".into(),
+            to: "b".into(),
+            topic: "news".into(),
+            conversation_id: String::new(),
+            r#type: "message".into(),
+            content: "hi".into(),
+            metadata: HashMap::new(),
+            timestamp: 0,
+            human_injected: false,
+            human_id: String::new(),
+        };
+        assert_eq!(CommHandler::name(&comm), "probe");
+        assert_eq!(CommHandler::version(&comm), "1.0.0");
+        CommHandler::on_start(&comm).await.unwrap();
+        CommHandler::connect(&comm, HashMap::new()).await.unwrap();
+        assert!(CommHandler::is_connected(&comm).await);
+        CommHandler::register(&comm, "agent-1", &["chat".into()]).await.unwrap();
+        CommHandler::subscribe(&comm, "news").await.unwrap();
+        CommHandler::send(&comm, msg).await.unwrap();
+        CommHandler::receive(&comm).await.unwrap();
+        CommHandler::unsubscribe(&comm, "news").await.unwrap();
+        CommHandler::deregister(&comm).await.unwrap();
+        CommHandler::disconnect(&comm).await.unwrap();
+        CommHandler::on_shutdown(&comm).await.unwrap();
+        assert_eq!(
+            calls(&comm),
+            [
+                "on_start", "connect", "is_connected", "register", "subscribe", "send",
+                "receive", "unsubscribe", "deregister", "disconnect", "on_shutdown",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn schedule_calls_run_on_the_pool() {
+        let schedule = Blocking::new(Probe::new());
+        let create = CreateScheduleRequest { name: "daily".into(), ..Default::default() };
+        let update = UpdateScheduleRequest { name: "daily".into(), ..Default::default() };
+        ScheduleHandler::on_start(&schedule).await.unwrap();
+        assert_eq!(ScheduleHandler::create(&schedule, create).await.unwrap().name, "daily");
+        assert_eq!(ScheduleHandler::get(&schedule, "daily").await.unwrap().name, "daily");
+        ScheduleHandler::list(&schedule, 10, 0, false).await.unwrap();
+        ScheduleHandler::update(&schedule, update).await.unwrap();
+        assert!(ScheduleHandler::enable(&schedule, "daily").await.unwrap().enabled);
+        ScheduleHandler::disable(&schedule, "daily").await.unwrap();
+        let (ok, message) = ScheduleHandler::trigger(&schedule, "daily").await.unwrap();
+        assert!(ok);
+        assert_eq!(message, "triggered daily");
+        ScheduleHandler::history(&schedule, "daily", 10, 0).await.unwrap();
+        ScheduleHandler::triggers(&schedule).await.unwrap();
+        ScheduleHandler::delete(&schedule, "daily").await.unwrap();
+        ScheduleHandler::on_shutdown(&schedule).await.unwrap();
+        assert_eq!(
+            calls(&schedule),
+            [
+                "on_start", "create", "get", "list", "update", "enable", "disable", "trigger",
+                "history", "triggers", "delete", "on_shutdown",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn panics_become_execution_errors() {
+        let pool = BlockingPool::new(1);
+        let tool = Blocking::new(Probe::panicking()).pool(pool.clone());
+        let err = ToolHandler::execute(&tool, json!({}), &ToolContext::default())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, NeboError::Execution(m) if m == "blocking handler panicked"),
+            "{err:?}"
+        );
+        assert!(!CommHandler::is_connected(&tool).await);
+        // The panicking calls gave their slot back.
+        assert_eq!(pool.run(|| Ok(7)).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn pool_bounds_concurrent_calls() {
+        let pool = BlockingPool::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut calls = tokio::task::JoinSet::new();
+        for _ in 0..6 {
+            let (pool, running, peak) = (pool.clone(), running.clone(), peak.clone());
+            calls.spawn(async move {
+                pool.run(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                })
+                .await
+            });
+        }
+        while let Some(call) = calls.join_next().await {
+            call.unwrap().unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn pool_has_at_least_one_slot() {
+        assert_eq!(BlockingPool::new(0).size(), 1);
+        assert_eq!(BlockingPool::new(3).size(), 3);
+    }
+}
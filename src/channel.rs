@@ -1,10 +1,13 @@
 use async_trait::async_trait;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status};
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
 use crate::pb;
+use crate::settings::Settings;
+use crate::shutdown::ShutdownSignal;
 
 /// Identifies who sent a message.
 #[derive(Debug, Clone, Default)]
@@ -56,11 +59,21 @@ pub trait ChannelHandler: Send + Sync + 'static {
     /// Send a message. Returns the platform-assigned message ID.
     async fn send(&self, env: ChannelEnvelope) -> Result<String, NeboError>;
     async fn receive(&self) -> Result<mpsc::Receiver<ChannelEnvelope>, NeboError>;
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once on shutdown after `Receive` streams are closed; a good
+    /// place to call [`ChannelHandler::disconnect`].
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
 }
 
 pub(crate) struct ChannelBridge {
-    pub handler: Box<dyn ChannelHandler>,
-    pub on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    pub handler: Arc<dyn ChannelHandler>,
+    pub settings: Settings,
+    pub shutdown: ShutdownSignal,
     pub env: AppEnv,
 }
 
@@ -170,8 +183,16 @@ impl pb::channel_service_server::ChannelService for ChannelBridge {
             .map_err(|e| Status::internal(e.to_string()))?;
 
         let (tx, stream_rx) = mpsc::channel(100);
+        let mut shutdown = self.shutdown.clone();
         tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
+            loop {
+                let msg = tokio::select! {
+                    msg = rx.recv() => match msg {
+                        Some(msg) => msg,
+                        None => break,
+                    },
+                    _ = shutdown.wait() => break,
+                };
                 let proto_msg = pb::InboundMessage {
                     channel_id: msg.channel_id,
                     user_id: msg.user_id,
@@ -212,9 +233,9 @@ impl pb::channel_service_server::ChannelService for ChannelBridge {
         &self,
         req: Request<pb::SettingsMap>,
     ) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
 }
@@ -1,10 +1,13 @@
 use async_trait::async_trait;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status};
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
 use crate::pb;
+use crate::settings::Settings;
+use crate::shutdown::ShutdownSignal;
 
 /// An inter-agent communication message.
 #[derive(Debug, Clone)]
@@ -29,18 +32,28 @@ pub trait CommHandler: Send + Sync + 'static {
     fn version(&self) -> &str;
     async fn connect(&self, config: std::collections::HashMap<String, String>) -> Result<(), NeboError>;
     async fn disconnect(&self) -> Result<(), NeboError>;
-    fn is_connected(&self) -> bool;
+    async fn is_connected(&self) -> bool;
     async fn send(&self, msg: CommMessage) -> Result<(), NeboError>;
     async fn subscribe(&self, topic: &str) -> Result<(), NeboError>;
     async fn unsubscribe(&self, topic: &str) -> Result<(), NeboError>;
     async fn register(&self, agent_id: &str, capabilities: &[String]) -> Result<(), NeboError>;
     async fn deregister(&self) -> Result<(), NeboError>;
     async fn receive(&self) -> Result<mpsc::Receiver<CommMessage>, NeboError>;
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once on shutdown after `Receive` streams are closed, e.g. to
+    /// deregister and disconnect.
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
 }
 
 pub(crate) struct CommBridge {
-    pub handler: Box<dyn CommHandler>,
-    pub on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    pub handler: Arc<dyn CommHandler>,
+    pub settings: Settings,
+    pub shutdown: ShutdownSignal,
     pub env: AppEnv,
 }
 
@@ -105,7 +118,7 @@ impl pb::comm_service_server::CommService for CommBridge {
     }
 
     async fn is_connected(&self, _req: Request<pb::Empty>) -> Result<Response<pb::CommIsConnectedResponse>, Status> {
-        Ok(Response::new(pb::CommIsConnectedResponse { connected: self.handler.is_connected() }))
+        Ok(Response::new(pb::CommIsConnectedResponse { connected: self.handler.is_connected().await }))
     }
 
     async fn send(&self, req: Request<pb::CommSendRequest>) -> Result<Response<pb::CommSendResponse>, Status> {
@@ -154,8 +167,16 @@ impl pb::comm_service_server::CommService for CommBridge {
     async fn receive(&self, _req: Request<pb::Empty>) -> Result<Response<Self::ReceiveStream>, Status> {
         let mut rx = self.handler.receive().await.map_err(|e| Status::internal(e.to_string()))?;
         let (tx, stream_rx) = mpsc::channel(100);
+        let mut shutdown = self.shutdown.clone();
         tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
+            loop {
+                let msg = tokio::select! {
+                    msg = rx.recv() => match msg {
+                        Some(msg) => msg,
+                        None => break,
+                    },
+                    _ = shutdown.wait() => break,
+                };
                 if tx.send(Ok(to_proto_comm(&msg))).await.is_err() {
                     break;
                 }
@@ -165,9 +186,9 @@ impl pb::comm_service_server::CommService for CommBridge {
     }
 
     async fn configure(&self, req: Request<pb::SettingsMap>) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
 }
@@ -0,0 +1,161 @@
+use serde_json::Value;
+use std::future::Future;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tonic::Status;
+
+use crate::env::AppEnv;
+use crate::error::NeboError;
+use crate::jobs::{JobContext, JobHandle, Jobs};
+use crate::output::{to_proto_block, ContentBlock, ToolOutput};
+use crate::pb;
+
+pub(crate) type EventSender = mpsc::UnboundedSender<Result<pb::ExecuteEvent, Status>>;
+
+/// Per-call context passed to [`ToolHandler::execute`](crate::tool::ToolHandler::execute).
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    /// UUID of the user the agent is acting for; empty if unknown.
+    pub user_id: String,
+    /// The user's plan tier.
+    pub plan: String,
+    /// The user's NeboLoop JWT, when Nebo sent one; forward it to your
+    /// backend for auth. Nebo only sends it to apps that declare the
+    /// `user:token` permission; this SDK passes through whatever arrives.
+    pub token: Option<String>,
+    pub env: AppEnv,
+    /// The plan from the [`Preview`](crate::approval::Preview) the user
+    /// approved, when Nebo executes a previewed call.
+    pub approved_plan: Option<Value>,
+    /// Fires when the call ends or is abandoned because it timed out or the
+    /// client went away. Pass it to spawned work and long loops. Shutdown
+    /// does not fire it: in-flight calls get the drain timeout to finish.
+    pub cancel: CancellationToken,
+    /// Deadline propagated from the caller's gRPC timeout, if it set one.
+    pub deadline: Option<Instant>,
+    /// Reports progress to Nebo while the tool runs.
+    pub progress: ProgressSink,
+    /// Name of the tool being called.
+    pub tool_name: String,
+    jobs: Jobs,
+}
+
+impl ToolContext {
+    pub(crate) fn new(env: AppEnv, user: Option<pb::UserContext>) -> Self {
+        let user = user.unwrap_or_default();
+        Self {
+            user_id: user.user_id,
+            plan: user.plan,
+            token: Some(user.token).filter(|t| !t.is_empty()),
+            env,
+            approved_plan: None,
+            cancel: CancellationToken::new(),
+            deadline: None,
+            progress: ProgressSink::default(),
+            tool_name: String::new(),
+            jobs: Jobs::default(),
+        }
+    }
+
+    pub(crate) fn with_jobs(mut self, jobs: Jobs) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Run `f` as a background job that outlives this call. Return the
+    /// handle from `execute` (`Ok(handle.into())`) and Nebo polls the job
+    /// for its result.
+    pub fn spawn_job<F, Fut>(&self, f: F) -> JobHandle
+    where
+        F: FnOnce(JobContext) -> Fut,
+        Fut: Future<Output = Result<ToolOutput, NeboError>> + Send + 'static,
+    {
+        self.jobs.spawn(&self.tool_name, f)
+    }
+}
+
+/// Streams progress, log lines and partial content from a running tool.
+///
+/// Events reach Nebo only when it called `ExecuteStream`; for unary `Execute`
+/// calls every method is a no-op, so handlers can report unconditionally.
+/// Return `true` from [`ToolHandler::reports_progress`](crate::tool::ToolHandler::reports_progress)
+/// so Nebo knows to use the streaming RPC.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSink {
+    tx: Option<EventSender>,
+}
+
+impl ProgressSink {
+    pub(crate) fn new(tx: EventSender) -> Self {
+        Self { tx: Some(tx) }
+    }
+
+    /// Whether anyone is listening to this sink.
+    pub fn is_active(&self) -> bool {
+        self.tx.as_ref().is_some_and(|tx| !tx.is_closed())
+    }
+
+    /// Report completion as a percentage (clamped to 0-100).
+    pub fn progress(&self, percent: f32, message: &str) {
+        self.emit(pb::execute_event::Event::Progress(pb::Progress {
+            percent: percent.clamp(0.0, 100.0),
+            message: message.to_string(),
+        }));
+    }
+
+    pub fn log(&self, line: &str) {
+        self.emit(pb::execute_event::Event::Log(line.to_string()));
+    }
+
+    /// Send a block of content ahead of the final result.
+    pub fn partial(&self, block: ContentBlock) {
+        self.emit(pb::execute_event::Event::Partial(to_proto_block(block)));
+    }
+
+    pub fn partial_text(&self, text: &str) {
+        self.partial(ContentBlock::Text(text.to_string()));
+    }
+
+    fn emit(&self, event: pb::execute_event::Event) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(Ok(pb::ExecuteEvent { event: Some(event) }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_context_fills_the_tool_context() {
+        let env = AppEnv {
+            name: "app".into(),
+            ..Default::default()
+        };
+        let user = pb::UserContext {
+            token: "jwt".into(),
+            user_id: "u-1".into(),
+            plan: "pro".into(),
+        };
+        let ctx = ToolContext::new(env, Some(user));
+        assert_eq!((ctx.user_id.as_str(), ctx.plan.as_str()), ("u-1", "pro"));
+        assert_eq!(ctx.token.as_deref(), Some("jwt"));
+        assert_eq!(ctx.env.name, "app");
+        assert!(ctx.deadline.is_none() && !ctx.cancel.is_cancelled());
+    }
+
+    #[test]
+    fn missing_user_or_token_leaves_fields_empty() {
+        let ctx = ToolContext::new(AppEnv::default(), None);
+        assert_eq!((ctx.user_id.as_str(), ctx.plan.as_str()), ("", ""));
+        assert_eq!(ctx.token, None);
+
+        let user = pb::UserContext {
+            user_id: "u-1".into(),
+            ..Default::default()
+        };
+        assert_eq!(ToolContext::new(AppEnv::default(), Some(user)).token, None);
+    }
+}
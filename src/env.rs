@@ -1,5 +1,5 @@
 /// Typed access to NEBO_APP_* environment variables set by Nebo's sandbox.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AppEnv {
     pub dir: String,
     pub sock_path: String,
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// SDK error type for Nebo app operations.
 #[derive(Debug, thiserror::Error)]
 pub enum NeboError {
@@ -7,12 +9,24 @@ pub enum NeboError {
     #[error("no capability handlers registered")]
     NoHandlers,
 
+    #[error("duplicate tool name: {0}")]
+    DuplicateTool(String),
+
+    #[error("invalid input for tool `{tool}`: {message}. Fix the arguments to match the tool's input schema and call it again.")]
+    InvalidInput { tool: String, message: String },
+
     #[error("transport error: {0}")]
     Transport(#[from] tonic::transport::Error),
 
     #[error("execution error: {0}")]
     Execution(String),
 
+    #[error("tool `{tool}` timed out after {after:?}")]
+    Timeout { tool: String, after: Duration },
+
+    #[error("invalid settings: {0}")]
+    InvalidSettings(String),
+
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
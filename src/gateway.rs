@@ -1,10 +1,15 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status};
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
 use crate::pb;
+use crate::settings::Settings;
+use crate::shutdown::ShutdownSignal;
 
 /// An LLM chat completion request from Nebo.
 #[derive(Debug, Clone)]
@@ -28,6 +33,41 @@ pub struct GatewayMessage {
     pub tool_calls: String,
 }
 
+impl GatewayMessage {
+    /// Parse the JSON-encoded tool calls of an assistant message. A message
+    /// without tool calls yields an empty list.
+    pub fn parse_tool_calls(&self) -> Result<Vec<GatewayToolCall>, NeboError> {
+        if self.tool_calls.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&self.tool_calls)
+            .map_err(|e| NeboError::Other(format!("invalid tool_calls in {} message: {}", self.role, e)))
+    }
+}
+
+/// A tool call made by the model, as listed in [`GatewayMessage::tool_calls`]
+/// and sent in `tool_call` events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GatewayToolCall {
+    pub id: String,
+    pub name: String,
+    /// Tool input. Arguments encoded as a JSON string are decoded, and
+    /// missing arguments are an empty object.
+    #[serde(default = "no_arguments", deserialize_with = "decode_arguments")]
+    pub arguments: Value,
+}
+
+fn no_arguments() -> Value {
+    json!({})
+}
+
+fn decode_arguments<'de, D: Deserializer<'de>>(d: D) -> Result<Value, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) => serde_json::from_str(&s).unwrap_or(Value::String(s)),
+        v => v,
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct GatewayToolDef {
     pub name: String,
@@ -35,7 +75,9 @@ pub struct GatewayToolDef {
     pub input_schema: Vec<u8>,
 }
 
-/// A streamed event sent back to Nebo.
+/// A streamed event sent back to Nebo. Build one with the constructors
+/// rather than setting `type` and `content` by hand: an event whose `type`
+/// and `content` do not parse reaches Nebo as an `error` event.
 #[derive(Debug, Clone)]
 pub struct GatewayEvent {
     pub r#type: String,
@@ -44,16 +86,199 @@ pub struct GatewayEvent {
     pub request_id: String,
 }
 
+impl GatewayEvent {
+    pub fn new(request_id: &str, kind: GatewayEventKind) -> Self {
+        Self {
+            r#type: kind.type_name().to_string(),
+            content: kind.content(),
+            model: String::new(),
+            request_id: request_id.to_string(),
+        }
+    }
+
+    pub fn text(request_id: &str, text: &str) -> Self {
+        Self::new(request_id, GatewayEventKind::Text(text.to_string()))
+    }
+
+    pub fn tool_call(request_id: &str, id: &str, name: &str, arguments: Value) -> Self {
+        let kind = GatewayEventKind::ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        };
+        Self::new(request_id, kind)
+    }
+
+    pub fn thinking(request_id: &str, text: &str) -> Self {
+        Self::new(request_id, GatewayEventKind::Thinking(text.to_string()))
+    }
+
+    pub fn error(request_id: &str, message: &str) -> Self {
+        Self::new(request_id, GatewayEventKind::Error(message.to_string()))
+    }
+
+    pub fn done(request_id: &str) -> Self {
+        Self::new(request_id, GatewayEventKind::Done)
+    }
+
+    pub fn usage(request_id: &str, input_tokens: u64, output_tokens: u64) -> Self {
+        Self::new(request_id, GatewayEventKind::Usage { input_tokens, output_tokens })
+    }
+
+    /// Name the model that handled the request.
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    /// Parse `type` and `content`.
+    pub fn kind(&self) -> Result<GatewayEventKind, NeboError> {
+        GatewayEventKind::parse(&self.r#type, &self.content)
+    }
+}
+
+/// What a [`GatewayEvent`] carries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GatewayEventKind {
+    /// A chunk of the reply.
+    Text(String),
+    /// The model asks Nebo to call a tool.
+    ToolCall { id: String, name: String, arguments: Value },
+    /// A chunk of the model's reasoning.
+    Thinking(String),
+    /// The request failed.
+    Error(String),
+    /// The reply is complete.
+    Done,
+    /// Tokens used by the request.
+    Usage { input_tokens: u64, output_tokens: u64 },
+}
+
+impl GatewayEventKind {
+    /// The event's `type` on the wire.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "text",
+            Self::ToolCall { .. } => "tool_call",
+            Self::Thinking(_) => "thinking",
+            Self::Error(_) => "error",
+            Self::Done => "done",
+            Self::Usage { .. } => "usage",
+        }
+    }
+
+    fn content(&self) -> String {
+        match self {
+            Self::Text(s) | Self::Thinking(s) | Self::Error(s) => s.clone(),
+            Self::ToolCall { id, name, arguments } => {
+                json!({"id": id, "name": name, "arguments": arguments}).to_string()
+            }
+            Self::Done => String::new(),
+            Self::Usage { input_tokens, output_tokens } => {
+                json!({"input_tokens": input_tokens, "output_tokens": output_tokens}).to_string()
+            }
+        }
+    }
+
+    /// Parse an event's `type` and `content`, rejecting unknown types and
+    /// malformed `tool_call` or `usage` content.
+    pub fn parse(r#type: &str, content: &str) -> Result<Self, NeboError> {
+        let invalid = |e: serde_json::Error| NeboError::Other(format!("invalid {} event: {}", r#type, e));
+        Ok(match r#type {
+            "text" => Self::Text(content.to_string()),
+            "thinking" => Self::Thinking(content.to_string()),
+            "error" => Self::Error(content.to_string()),
+            "done" => Self::Done,
+            "tool_call" => {
+                let call: GatewayToolCall = serde_json::from_str(content).map_err(invalid)?;
+                call.into()
+            }
+            "usage" => {
+                #[derive(Deserialize)]
+                struct Usage {
+                    #[serde(default)]
+                    input_tokens: u64,
+                    #[serde(default)]
+                    output_tokens: u64,
+                }
+                let u: Usage = serde_json::from_str(content).map_err(invalid)?;
+                Self::Usage {
+                    input_tokens: u.input_tokens,
+                    output_tokens: u.output_tokens,
+                }
+            }
+            other => return Err(NeboError::Other(format!("unknown gateway event type `{}`", other))),
+        })
+    }
+}
+
+impl From<GatewayToolCall> for GatewayEventKind {
+    fn from(call: GatewayToolCall) -> Self {
+        Self::ToolCall {
+            id: call.id,
+            name: call.name,
+            arguments: call.arguments,
+        }
+    }
+}
+
+impl TryFrom<&GatewayEvent> for GatewayEventKind {
+    type Error = NeboError;
+
+    fn try_from(event: &GatewayEvent) -> Result<Self, NeboError> {
+        event.kind()
+    }
+}
+
+impl TryFrom<&pb::GatewayEvent> for GatewayEventKind {
+    type Error = NeboError;
+
+    fn try_from(event: &pb::GatewayEvent) -> Result<Self, NeboError> {
+        Self::parse(&event.r#type, &event.content)
+    }
+}
+
+impl From<GatewayEvent> for pb::GatewayEvent {
+    fn from(event: GatewayEvent) -> Self {
+        Self {
+            r#type: event.r#type,
+            content: event.content,
+            model: event.model,
+            request_id: event.request_id,
+        }
+    }
+}
+
+impl From<pb::GatewayEvent> for GatewayEvent {
+    fn from(event: pb::GatewayEvent) -> Self {
+        Self {
+            r#type: event.r#type,
+            content: event.content,
+            model: event.model,
+            request_id: event.request_id,
+        }
+    }
+}
+
 /// Trait for gateway capability handlers.
 #[async_trait]
 pub trait GatewayHandler: Send + Sync + 'static {
     async fn stream(&self, req: GatewayRequest) -> Result<mpsc::Receiver<GatewayEvent>, NeboError>;
     async fn cancel(&self, request_id: &str) -> Result<(), NeboError>;
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once on shutdown after open streams are closed.
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
 }
 
 pub(crate) struct GatewayBridge {
-    pub handler: Box<dyn GatewayHandler>,
-    pub on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    pub handler: Arc<dyn GatewayHandler>,
+    pub settings: Settings,
+    pub shutdown: ShutdownSignal,
     pub env: AppEnv,
 }
 
@@ -105,15 +330,27 @@ impl pb::gateway_service_server::GatewayService for GatewayBridge {
             .map_err(|e| Status::internal(e.to_string()))?;
 
         let (tx, stream_rx) = tokio::sync::mpsc::channel(32);
+        let mut shutdown = self.shutdown.clone();
+        let app = self.env.name.clone();
         tokio::spawn(async move {
-            while let Some(event) = rx.recv().await {
-                let proto = pb::GatewayEvent {
-                    r#type: event.r#type,
-                    content: event.content,
-                    model: event.model,
-                    request_id: event.request_id,
+            loop {
+                let event = tokio::select! {
+                    event = rx.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                    _ = shutdown.wait() => break,
                 };
-                if tx.send(Ok(proto)).await.is_err() {
+                // Nebo cannot act on a mistyped event, so report it as an error.
+                let event = match event.kind() {
+                    Ok(_) => event,
+                    Err(e) => {
+                        eprintln!("[{}] {}", app, e);
+                        GatewayEvent::error(&event.request_id, &e.to_string())
+                            .with_model(&event.model)
+                    }
+                };
+                if tx.send(Ok(event.into())).await.is_err() {
                     break;
                 }
             }
@@ -146,9 +383,9 @@ impl pb::gateway_service_server::GatewayService for GatewayBridge {
         &self,
         req: Request<pb::SettingsMap>,
     ) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
 }
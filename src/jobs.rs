@@ -0,0 +1,550 @@
+//! Background jobs for tools that outlive a single `Execute` call.
+//!
+//! A tool starts a job with [`ToolContext::spawn_job`](crate::ToolContext::spawn_job)
+//! and returns the [`JobHandle`] as its output. Nebo then polls the job
+//! through the `JobStatus`, `JobResult` and `CancelJob` RPCs. Each change of
+//! a job's state is written to `<data_dir>/jobs/` so finished results survive
+//! a restart; jobs still running when the app stopped are reported as
+//! interrupted. Progress is only kept in memory.
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+
+use crate::env::AppEnv;
+use crate::error::NeboError;
+use crate::output::ToolOutput;
+use crate::pb;
+
+/// Finished jobs are forgotten this long after they finish.
+const RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Lifecycle state of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The app stopped while the job was running.
+    Interrupted,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+            JobState::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != JobState::Running
+    }
+}
+
+/// Persisted description of a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JobRecord {
+    id: String,
+    tool: String,
+    state: JobState,
+    percent: f32,
+    message: String,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl JobRecord {
+    fn info(&self) -> pb::JobInfo {
+        pb::JobInfo {
+            job_id: self.id.clone(),
+            tool_name: self.tool.clone(),
+            state: self.state.as_str().to_string(),
+            percent: self.percent,
+            message: self.message.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+struct JobEntry {
+    record: JobRecord,
+    cancel: CancellationToken,
+    result: Option<pb::ExecuteResponse>,
+}
+
+#[derive(Default)]
+struct JobsInner {
+    app: String,
+    dir: Option<PathBuf>,
+    /// Whether `dir` exists or could be created, checked on first write.
+    writable: OnceLock<bool>,
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    next: AtomicU64,
+}
+
+/// The app's job table. Jobs are kept in memory only when the app has no
+/// data directory or it cannot be written.
+#[derive(Clone, Default)]
+pub struct Jobs {
+    inner: Arc<JobsInner>,
+}
+
+impl std::fmt::Debug for Jobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jobs").field("dir", &self.inner.dir).finish()
+    }
+}
+
+/// Identifies a started job. Convert it into the tool's output to tell Nebo
+/// to poll for the result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobHandle {
+    pub id: String,
+}
+
+impl From<JobHandle> for ToolOutput {
+    fn from(job: JobHandle) -> Self {
+        let mut out = ToolOutput::text(format!(
+            "Started background job {}. The result will be available when it finishes.",
+            job.id
+        ));
+        out.job_id = Some(job.id);
+        out
+    }
+}
+
+/// Handed to a running job to report progress and observe cancellation.
+#[derive(Clone)]
+pub struct JobContext {
+    pub id: String,
+    /// Fires when Nebo cancels the job.
+    pub cancel: CancellationToken,
+    jobs: Jobs,
+}
+
+impl JobContext {
+    /// Record progress as a percentage (clamped to 0-100) and a status line.
+    pub fn progress(&self, percent: f32, message: &str) {
+        let mut table = self.jobs.inner.jobs.lock().unwrap();
+        if let Some(entry) = table.get_mut(&self.id) {
+            let r = &mut entry.record;
+            if r.state == JobState::Running {
+                r.percent = percent.clamp(0.0, 100.0);
+                r.message = message.to_string();
+                r.updated_at = now();
+            }
+        }
+    }
+}
+
+impl Jobs {
+    /// Load the job table from `<data_dir>/jobs`, marking jobs that were
+    /// running when the app stopped as interrupted. A directory that cannot
+    /// be read is logged and leaves the table empty; it is only created
+    /// when the first job starts.
+    pub(crate) fn open(env: &AppEnv) -> Self {
+        if env.data_dir.is_empty() {
+            return Self::default();
+        }
+        let dir = Path::new(&env.data_dir).join("jobs");
+        let jobs = Self {
+            inner: Arc::new(JobsInner {
+                app: env.name.clone(),
+                dir: Some(dir.clone()),
+                ..Default::default()
+            }),
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return jobs,
+            Err(e) => {
+                eprintln!(
+                    "[{}] cannot read jobs from {}: {}",
+                    env.name,
+                    dir.display(),
+                    e
+                );
+                return jobs;
+            }
+        };
+
+        let now = now();
+        let mut table = jobs.inner.jobs.lock().unwrap();
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let Some(mut record) = std::fs::read(&path)
+                .ok()
+                .and_then(|b| serde_json::from_slice::<JobRecord>(&b).ok())
+            else {
+                continue;
+            };
+            if expired(&record, now) {
+                remove_files(&path);
+                continue;
+            }
+            if record.state == JobState::Running {
+                record.state = JobState::Interrupted;
+                record.updated_at = now;
+                jobs.persist(&record, None);
+            }
+            let result = std::fs::read(path.with_extension("result"))
+                .ok()
+                .and_then(|b| pb::ExecuteResponse::decode(b.as_slice()).ok());
+            table.insert(
+                record.id.clone(),
+                JobEntry {
+                    record,
+                    cancel: CancellationToken::new(),
+                    result,
+                },
+            );
+        }
+        drop(table);
+        jobs
+    }
+
+    /// Run `f` in the background as a job of `tool`.
+    pub fn spawn<F, Fut>(&self, tool: &str, f: F) -> JobHandle
+    where
+        F: FnOnce(JobContext) -> Fut,
+        Fut: Future<Output = Result<ToolOutput, NeboError>> + Send + 'static,
+    {
+        let id = format!(
+            "job-{:x}-{}",
+            now_millis(),
+            self.inner.next.fetch_add(1, Ordering::Relaxed)
+        );
+        let now = now();
+        let record = JobRecord {
+            id: id.clone(),
+            tool: tool.to_string(),
+            state: JobState::Running,
+            percent: 0.0,
+            message: String::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        let cancel = CancellationToken::new();
+        self.persist(&record, None);
+        let expired = {
+            let mut table = self.inner.jobs.lock().unwrap();
+            let expired: Vec<String> = table
+                .values()
+                .filter(|e| expired(&e.record, now))
+                .map(|e| e.record.id.clone())
+                .collect();
+            for id in &expired {
+                table.remove(id);
+            }
+            table.insert(
+                id.clone(),
+                JobEntry {
+                    record,
+                    cancel: cancel.clone(),
+                    result: None,
+                },
+            );
+            expired
+        };
+        if let Some(dir) = &self.inner.dir {
+            for id in expired {
+                remove_files(&dir.join(format!("{id}.json")));
+            }
+        }
+
+        let fut = f(JobContext {
+            id: id.clone(),
+            cancel: cancel.clone(),
+            jobs: self.clone(),
+        });
+        let jobs = self.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let result = tokio::select! {
+                result = fut => result,
+                _ = cancel.cancelled() => return,
+            };
+            jobs.finish(&job_id, result);
+        });
+        JobHandle { id }
+    }
+
+    pub(crate) fn status(&self, id: &str) -> Option<pb::JobInfo> {
+        let table = self.inner.jobs.lock().unwrap();
+        table.get(id).map(|e| e.record.info())
+    }
+
+    /// The final response of a finished job; `Ok(None)` while it runs.
+    pub(crate) fn result(&self, id: &str) -> Option<Option<pb::ExecuteResponse>> {
+        let table = self.inner.jobs.lock().unwrap();
+        let entry = table.get(id)?;
+        Some(match entry.record.state {
+            JobState::Running => None,
+            JobState::Succeeded | JobState::Failed => entry.result.clone(),
+            JobState::Cancelled => Some(stopped("job was cancelled", "cancelled")),
+            JobState::Interrupted => Some(stopped(
+                "job was interrupted because the app restarted",
+                "interrupted",
+            )),
+        })
+    }
+
+    pub(crate) fn cancel(&self, id: &str) -> Option<pb::JobInfo> {
+        let (record, changed) = {
+            let mut table = self.inner.jobs.lock().unwrap();
+            let entry = table.get_mut(id)?;
+            let running = entry.record.state == JobState::Running;
+            if running {
+                entry.cancel.cancel();
+                entry.record.state = JobState::Cancelled;
+                entry.record.updated_at = now();
+            }
+            (entry.record.clone(), running)
+        };
+        if changed {
+            self.persist(&record, None);
+        }
+        Some(record.info())
+    }
+
+    fn finish(&self, id: &str, result: Result<ToolOutput, NeboError>) {
+        let (state, response) = match result {
+            Ok(output) => (JobState::Succeeded, output.into_response()),
+            Err(e) => (JobState::Failed, stopped(&e.to_string(), "")),
+        };
+        let record = {
+            let mut table = self.inner.jobs.lock().unwrap();
+            let Some(entry) = table.get_mut(id) else {
+                return;
+            };
+            if entry.record.state != JobState::Running {
+                return;
+            }
+            entry.record.state = state;
+            entry.record.percent = 100.0;
+            entry.record.updated_at = now();
+            entry.result = Some(response.clone());
+            entry.record.clone()
+        };
+        self.persist(&record, Some(&response));
+    }
+
+    /// Write a record (and result) to disk, creating the directory on first
+    /// use. Failures are logged, not fatal: the in-memory table stays
+    /// authoritative for this run. Each job is written when it starts and
+    /// once more when it stops, so its writes never race.
+    fn persist(&self, record: &JobRecord, result: Option<&pb::ExecuteResponse>) {
+        let Some(dir) = &self.inner.dir else {
+            return;
+        };
+        let writable = self
+            .inner
+            .writable
+            .get_or_init(|| match std::fs::create_dir_all(dir) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!(
+                        "[{}] cannot create {}: {}; keeping jobs in memory",
+                        self.inner.app,
+                        dir.display(),
+                        e
+                    );
+                    false
+                }
+            });
+        if !writable {
+            return;
+        }
+        let path = dir.join(format!("{}.json", record.id));
+        let written = result
+            .map(|r| write_atomic(&path.with_extension("result"), &r.encode_to_vec()))
+            .unwrap_or(Ok(()))
+            .and_then(|_| write_atomic(&path, &serde_json::to_vec(record).unwrap_or_default()));
+        if let Err(e) = written {
+            eprintln!(
+                "[{}] failed to persist job {}: {}",
+                self.inner.app, record.id, e
+            );
+        }
+    }
+}
+
+fn stopped(message: &str, code: &str) -> pb::ExecuteResponse {
+    pb::ExecuteResponse {
+        content: message.to_string(),
+        is_error: true,
+        error_code: code.to_string(),
+        ..Default::default()
+    }
+}
+
+fn expired(record: &JobRecord, now: i64) -> bool {
+    record.state.is_finished() && now - record.updated_at > RETENTION_SECS
+}
+
+/// Remove a job's record and result files.
+fn remove_files(record: &Path) {
+    let _ = std::fs::remove_file(record);
+    let _ = std::fs::remove_file(record.with_extension("result"));
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    // `<id>.json.tmp`, so a record and its result never share a temp file.
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(tmp, path)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(data_dir: &Path) -> AppEnv {
+        AppEnv {
+            name: "jobs-test".into(),
+            data_dir: data_dir.to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nebo-jobs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    async fn finished(jobs: &Jobs, id: &str) -> pb::ExecuteResponse {
+        let wait = async {
+            loop {
+                if let Some(Some(response)) = jobs.result(id) {
+                    return response;
+                }
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(5), wait)
+            .await
+            .expect("job did not finish")
+    }
+
+    #[tokio::test]
+    async fn unwritable_data_dir_keeps_jobs_in_memory() {
+        let file = scratch("unwritable");
+        std::fs::write(&file, b"not a directory").unwrap();
+
+        let jobs = Jobs::open(&env(&file));
+        let job = jobs.spawn("t", |_| async { Ok(ToolOutput::text("done")) });
+        assert_eq!(finished(&jobs, &job.id).await.content, "done");
+        assert!(file.is_file());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn directory_is_created_by_the_first_job() {
+        let dir = scratch("lazy");
+        let jobs = Jobs::open(&env(&dir));
+        assert!(!dir.exists());
+
+        let job = jobs.spawn("t", |_| async { Ok(ToolOutput::text("done")) });
+        finished(&jobs, &job.id).await;
+        assert!(dir
+            .join("jobs")
+            .join(format!("{}.result", job.id))
+            .is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn results_survive_reopen_and_running_jobs_are_interrupted() {
+        let dir = scratch("reopen");
+        let jobs = Jobs::open(&env(&dir));
+        let done = jobs.spawn("t", |_| async { Ok(ToolOutput::text("done")) });
+        finished(&jobs, &done.id).await;
+        let running = jobs.spawn("t", |ctx| async move {
+            ctx.progress(40.0, "working");
+            std::future::pending().await
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(jobs.status(&running.id).unwrap().percent, 40.0);
+
+        let reopened = Jobs::open(&env(&dir));
+        assert_eq!(reopened.result(&done.id).unwrap().unwrap().content, "done");
+        let info = reopened.status(&running.id).unwrap();
+        assert_eq!(info.state, "interrupted");
+        // Progress is not written to disk.
+        assert_eq!(info.percent, 0.0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn record_and_result_use_their_own_temp_files() {
+        let dir = scratch("atomic");
+        std::fs::create_dir_all(&dir).unwrap();
+        let record = dir.join("job-1.json");
+        write_atomic(&record, b"record").unwrap();
+        write_atomic(&record.with_extension("result"), b"result").unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["job-1.json", "job-1.result"]);
+        assert_eq!(std::fs::read(&record).unwrap(), b"record");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_jobs_are_evicted() {
+        let dir = scratch("evict");
+        let jobs = Jobs::open(&env(&dir));
+        let old = jobs.spawn("t", |_| async { Ok(ToolOutput::text("old")) });
+        finished(&jobs, &old.id).await;
+        let expired_at = now() - RETENTION_SECS - 1;
+        jobs.inner
+            .jobs
+            .lock()
+            .unwrap()
+            .get_mut(&old.id)
+            .unwrap()
+            .record
+            .updated_at = expired_at;
+
+        jobs.spawn("t", |_| async { Ok(ToolOutput::text("new")) });
+        assert!(jobs.status(&old.id).is_none());
+        assert!(!dir.join("jobs").join(format!("{}.json", old.id)).exists());
+        assert!(!dir.join("jobs").join(format!("{}.result", old.id)).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -4,18 +4,42 @@ pub mod pb {
 }
 
 pub mod app;
+pub mod approval;
+pub mod blocking;
 pub mod channel;
 pub mod comm;
+pub mod context;
 pub mod env;
 pub mod error;
 pub mod gateway;
+pub mod jobs;
+pub mod mcp;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod output;
+pub mod registry;
+pub mod router;
 pub mod schema;
 pub mod schedule;
+pub mod settings;
+mod shutdown;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod tool;
 pub mod ui;
+pub mod validate;
 
 // Re-exports for convenience
 pub use app::NeboApp;
+pub use approval::{Approval, Preview};
+pub use context::ToolContext;
 pub use env::AppEnv;
 pub use error::NeboError;
-pub use schema::SchemaBuilder;
+pub use output::ToolOutput;
+pub use registry::ToolRegistry;
+pub use router::ActionRouter;
+pub use nebo_sdk_macros::nebo_tool;
+pub use schema::{NeboSchema, Prop, SchemaBuilder};
+pub use settings::Settings;
+pub use tool::{tool_fn, typed_tool_fn};
+pub use ui::ui_fn;
@@ -0,0 +1,138 @@
+//! Model Context Protocol interop.
+//!
+//! [`client`] runs an existing MCP server as a subprocess and serves its
+//! tools as Nebo tools. In the other direction,
+//! [`NeboApp::run_mcp_stdio`](crate::NeboApp::run_mcp_stdio) serves an app's
+//! tools to MCP hosts.
+
+pub mod client;
+pub(crate) mod server;
+
+pub use client::{McpClient, McpCommand, McpTool};
+
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::error::NeboError;
+use crate::output::ToolOutput;
+use crate::pb;
+
+/// MCP revision spoken by the SDK.
+pub(crate) const PROTOCOL_VERSION: &str = "2025-06-18";
+/// Older revisions the server also accepts.
+const SUPPORTED_VERSIONS: [&str; 3] = [PROTOCOL_VERSION, "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn request(id: u64, method: &str, params: Value) -> String {
+    json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}).to_string()
+}
+
+fn notification(method: &str, params: Value) -> String {
+    json!({"jsonrpc": "2.0", "method": method, "params": params}).to_string()
+}
+
+fn response(id: &Value, result: Value) -> String {
+    json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string()
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> String {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}}).to_string()
+}
+
+/// Convert a `tools/call` result into a tool output. Results flagged with
+/// `isError` become [`NeboError::Execution`] with the result text.
+fn output_from_result(result: &Value) -> Result<ToolOutput, NeboError> {
+    let mut out = ToolOutput::new();
+    let blocks = result.get("content").and_then(Value::as_array);
+    for block in blocks.into_iter().flatten() {
+        let str_field = |key: &str| block.get(key).and_then(Value::as_str).unwrap_or_default();
+        match str_field("type") {
+            "text" => out = out.with_text(str_field("text")),
+            "image" => out = out.with_image(str_field("mimeType"), decode(str_field("data"))?),
+            "audio" => out = out.with_file_data("audio", str_field("mimeType"), decode(str_field("data"))?),
+            "resource_link" => {
+                out = out.with_file(str_field("name"), str_field("mimeType"), str_field("uri"))
+            }
+            "resource" => {
+                let resource = &block["resource"];
+                let field = |key: &str| resource.get(key).and_then(Value::as_str);
+                let uri = field("uri").unwrap_or_default();
+                let mime_type = field("mimeType").unwrap_or_default();
+                match (field("text"), field("blob")) {
+                    (Some(text), _) if mime_type.is_empty() || mime_type.starts_with("text/") => {
+                        out = out.with_text(text)
+                    }
+                    (Some(text), _) => {
+                        out = out.with_file_data(file_name(uri), mime_type, text.as_bytes().to_vec())
+                    }
+                    (None, Some(blob)) => out = out.with_file_data(file_name(uri), mime_type, decode(blob)?),
+                    (None, None) => out = out.with_file(file_name(uri), mime_type, uri),
+                }
+            }
+            other => out = out.with_text(format!("[unsupported MCP content: {other}]")),
+        }
+    }
+    // Servers should mirror structured content as text, but not all do.
+    if out.content.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            out = out.with_json(structured.clone());
+        }
+    }
+
+    if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+        return Err(NeboError::Execution(out.to_text()));
+    }
+    Ok(out)
+}
+
+/// Convert an execute response into a `tools/call` result.
+fn result_from_response(resp: pb::ExecuteResponse) -> Value {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut structured = None;
+    let mut content: Vec<Value> = resp
+        .blocks
+        .into_iter()
+        .map(|block| match block.r#type.as_str() {
+            "image" => json!({"type": "image", "mimeType": block.mime_type, "data": engine.encode(&block.data)}),
+            "json" => {
+                structured = serde_json::from_str::<Value>(&block.text).ok().filter(Value::is_object);
+                json!({"type": "text", "text": block.text})
+            }
+            "file" if block.uri.is_empty() => json!({
+                "type": "resource",
+                "resource": {
+                    "uri": format!("file:///{}", block.name),
+                    "mimeType": block.mime_type,
+                    "blob": engine.encode(&block.data),
+                },
+            }),
+            "file" => json!({"type": "resource_link", "uri": block.uri, "name": block.name, "mimeType": block.mime_type}),
+            "citation" => json!({"type": "resource_link", "uri": block.uri, "name": block.text}),
+            _ => json!({"type": "text", "text": block.text}),
+        })
+        .collect();
+    if content.is_empty() && !resp.content.is_empty() {
+        content.push(json!({"type": "text", "text": resp.content}));
+    }
+
+    // Only a single JSON block is unambiguous structured content.
+    let structured = structured.filter(|_| content.len() == 1);
+    let mut result = json!({"content": content, "isError": resp.is_error});
+    if let Some(value) = structured {
+        result["structuredContent"] = value;
+    }
+    result
+}
+
+fn decode(data: &str) -> Result<Vec<u8>, NeboError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| NeboError::Execution(format!("invalid base64 in MCP content: {e}")))
+}
+
+fn file_name(uri: &str) -> &str {
+    uri.rsplit('/').find(|s| !s.is_empty()).unwrap_or(uri)
+}
@@ -0,0 +1,603 @@
+//! Serve the tools of an MCP server that speaks JSON-RPC over stdio.
+//!
+//! [`McpClient::spawn`] starts the server, performs the `initialize`
+//! handshake and keeps the process running; [`McpClient::tools`] lists its
+//! tools as a [`ToolRegistry`] whose handlers execute through `tools/call`.
+//!
+//! If the server exits, calls in flight fail and the next call starts it
+//! again. Failed calls are not retried, since the server may have acted on
+//! them. A server that keeps exiting shortly after starting is given up on
+//! after [`McpCommand::max_restarts`] attempts.
+//!
+//! ```no_run
+//! # use nebo_sdk::mcp::{McpClient, McpCommand};
+//! # async fn run() -> Result<(), nebo_sdk::NeboError> {
+//! let server = McpClient::spawn(
+//!     McpCommand::new("npx").args(["-y", "@modelcontextprotocol/server-filesystem", "/data"]),
+//! )
+//! .await?;
+//! nebo_sdk::NeboApp::new()?
+//!     .register_tools(server.tools().await?)
+//!     .run()
+//!     .await
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use super::{error_response, notification, output_from_result, request, response};
+use super::{METHOD_NOT_FOUND, PROTOCOL_VERSION};
+use crate::context::ToolContext;
+use crate::error::NeboError;
+use crate::output::ToolOutput;
+use crate::registry::ToolRegistry;
+use crate::tool::ToolHandler;
+
+/// How long the server may take to answer `initialize`.
+const INIT_TIMEOUT: Duration = Duration::from_secs(30);
+/// A server that ran at least this long before exiting counts as healthy,
+/// resetting the restart budget.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// How to start an MCP server.
+#[derive(Debug, Clone)]
+pub struct McpCommand {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    dir: Option<PathBuf>,
+    max_restarts: u32,
+}
+
+impl McpCommand {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            dir: None,
+            max_restarts: 5,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set an environment variable for the server process.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Working directory of the server process.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Consecutive restarts allowed before calls fail instead of starting the
+    /// server again (default 5).
+    pub fn max_restarts(mut self, n: u32) -> Self {
+        self.max_restarts = n;
+        self
+    }
+
+    /// Short name used in errors and logs.
+    fn label(&self) -> &str {
+        self.program.rsplit('/').next().unwrap_or(&self.program)
+    }
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, NeboError>>>>>;
+
+/// One running server process.
+struct Connection {
+    /// Lines to write to the server's stdin.
+    writer: mpsc::UnboundedSender<String>,
+    pending: Pending,
+    /// Fires when the process has exited.
+    closed: CancellationToken,
+    /// Cancel to kill the process.
+    kill: CancellationToken,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    conn: Option<Arc<Connection>>,
+    restarts: u32,
+}
+
+struct ClientInner {
+    command: McpCommand,
+    state: tokio::sync::Mutex<State>,
+    next_id: AtomicU64,
+}
+
+/// A connection to an MCP server subprocess. Cheap to clone; clones share
+/// the process.
+#[derive(Clone)]
+pub struct McpClient {
+    inner: Arc<ClientInner>,
+}
+
+impl std::fmt::Debug for McpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClient").field("command", &self.inner.command).finish()
+    }
+}
+
+impl McpClient {
+    /// Start the server and complete the `initialize` handshake.
+    pub async fn spawn(command: McpCommand) -> Result<Self, NeboError> {
+        let client = Self {
+            inner: Arc::new(ClientInner {
+                command,
+                state: Default::default(),
+                next_id: AtomicU64::new(1),
+            }),
+        };
+        client.connection().await?;
+        Ok(client)
+    }
+
+    /// List the server's tools as Nebo tool handlers.
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, NeboError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(c) => json!({"cursor": c}),
+                None => json!({}),
+            };
+            let page = self.request("tools/list", params).await?;
+            for tool in page.get("tools").and_then(Value::as_array).into_iter().flatten() {
+                tools.push(McpTool::from_definition(self.clone(), tool)?);
+            }
+            cursor = page.get("nextCursor").and_then(Value::as_str).map(String::from);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// List the server's tools as a registry, ready for
+    /// [`NeboApp::register_tools`](crate::NeboApp::register_tools).
+    pub async fn tools(&self) -> Result<ToolRegistry, NeboError> {
+        let mut registry = ToolRegistry::new();
+        for tool in self.list_tools().await? {
+            registry.add(tool);
+        }
+        Ok(registry)
+    }
+
+    /// Call a tool by name.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<ToolOutput, NeboError> {
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": arguments}))
+            .await?;
+        output_from_result(&result)
+    }
+
+    /// Send a JSON-RPC request and wait for its result, starting the server
+    /// again if it has exited.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, NeboError> {
+        let conn = self.connection().await?;
+        self.send(&conn, method, params).await
+    }
+
+    /// Stop the server process. A later call starts it again.
+    pub async fn shutdown(&self) {
+        let mut state = self.inner.state.lock().await;
+        if let Some(conn) = state.conn.take() {
+            conn.kill.cancel();
+            conn.closed.cancelled().await;
+        }
+        state.restarts = 0;
+    }
+
+    /// The running connection, (re)starting the server if needed.
+    async fn connection(&self) -> Result<Arc<Connection>, NeboError> {
+        let command = &self.inner.command;
+        let mut state = self.inner.state.lock().await;
+        if let Some(conn) = &state.conn {
+            if !conn.closed.is_cancelled() {
+                return Ok(conn.clone());
+            }
+            if conn.started.elapsed() >= STABLE_AFTER {
+                state.restarts = 0;
+            }
+            if state.restarts >= command.max_restarts {
+                return Err(NeboError::Execution(format!(
+                    "MCP server `{}` keeps exiting; gave up after {} restarts",
+                    command.label(),
+                    state.restarts
+                )));
+            }
+            state.restarts += 1;
+            eprintln!(
+                "[mcp:{}] server exited, restarting ({}/{})",
+                command.label(),
+                state.restarts,
+                command.max_restarts
+            );
+        }
+        let conn = Arc::new(self.start().await?);
+        state.conn = Some(conn.clone());
+        Ok(conn)
+    }
+
+    async fn start(&self) -> Result<Connection, NeboError> {
+        let command = &self.inner.command;
+        let mut cmd = Command::new(&command.program);
+        cmd.args(&command.args)
+            .envs(command.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        if let Some(dir) = &command.dir {
+            cmd.current_dir(dir);
+        }
+        let mut child = cmd.spawn().map_err(|e| {
+            NeboError::Execution(format!("failed to start MCP server `{}`: {}", command.label(), e))
+        })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(NeboError::Other("MCP server stdio is not piped".into()));
+        };
+
+        let (writer, rx) = mpsc::unbounded_channel();
+        let conn = Connection {
+            writer: writer.clone(),
+            pending: Default::default(),
+            closed: CancellationToken::new(),
+            kill: CancellationToken::new(),
+            started: Instant::now(),
+        };
+        tokio::spawn(write_lines(stdin, rx));
+        tokio::spawn(read_messages(
+            stdout,
+            conn.pending.clone(),
+            writer,
+            command.label().to_string(),
+        ));
+        let (closed, kill) = (conn.closed.clone(), conn.kill.clone());
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = child.wait() => {}
+                _ = kill.cancelled() => {
+                    let _ = child.kill().await;
+                }
+            }
+            closed.cancel();
+        });
+
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "nebo-sdk", "version": env!("CARGO_PKG_VERSION")},
+        });
+        let init = tokio::time::timeout(INIT_TIMEOUT, self.send(&conn, "initialize", params));
+        match init.await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                conn.kill.cancel();
+                return Err(e);
+            }
+            Err(_) => {
+                conn.kill.cancel();
+                return Err(NeboError::Execution(format!(
+                    "MCP server `{}` did not answer `initialize` within {:?}",
+                    command.label(),
+                    INIT_TIMEOUT
+                )));
+            }
+        }
+        let _ = conn.writer.send(notification("notifications/initialized", json!({})));
+        Ok(conn)
+    }
+
+    async fn send(&self, conn: &Connection, method: &str, params: Value) -> Result<Value, NeboError> {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        conn.pending.lock().unwrap().insert(id, tx);
+        let _guard = CancelOnDrop { id, conn };
+        let exited = || {
+            NeboError::Execution(format!(
+                "MCP server `{}` exited before answering `{}`",
+                self.inner.command.label(),
+                method
+            ))
+        };
+
+        if conn.writer.send(request(id, method, params)).is_err() {
+            return Err(exited());
+        }
+        let result = tokio::select! {
+            biased;
+            result = rx => result.unwrap_or_else(|_| Err(exited())),
+            _ = conn.closed.cancelled() => Err(exited()),
+        };
+        // Answered or unanswerable: nothing left to cancel.
+        conn.pending.lock().unwrap().remove(&id);
+        result
+    }
+}
+
+/// Tells the server to stop working on a request whose caller went away,
+/// e.g. because the tool call timed out or was cancelled.
+struct CancelOnDrop<'a> {
+    id: u64,
+    conn: &'a Connection,
+}
+
+impl Drop for CancelOnDrop<'_> {
+    fn drop(&mut self) {
+        if self.conn.pending.lock().unwrap().remove(&self.id).is_some() {
+            let _ = self.conn.writer.send(notification(
+                "notifications/cancelled",
+                json!({"requestId": self.id, "reason": "request cancelled by the caller"}),
+            ));
+        }
+    }
+}
+
+async fn write_lines(mut stdin: ChildStdin, mut rx: mpsc::UnboundedReceiver<String>) {
+    while let Some(mut line) = rx.recv().await {
+        line.push('\n');
+        if stdin.write_all(line.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Route responses to their waiting requests and answer requests the
+/// server sends us.
+async fn read_messages(stdout: ChildStdout, pending: Pending, writer: mpsc::UnboundedSender<String>, label: String) {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let msg: Value = match serde_json::from_str(&line) {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!("[mcp:{}] ignoring invalid message: {}", label, e);
+                continue;
+            }
+        };
+        let id = msg.get("id").cloned().unwrap_or(Value::Null);
+        match msg.get("method").and_then(Value::as_str) {
+            // Notifications need no answer.
+            Some(_) if id.is_null() => {}
+            Some("ping") => {
+                let _ = writer.send(response(&id, json!({})));
+            }
+            Some(method) => {
+                let message = format!("method `{method}` is not supported by this client");
+                let _ = writer.send(error_response(&id, METHOD_NOT_FOUND, &message));
+            }
+            None => {
+                let Some(tx) = id.as_u64().and_then(|id| pending.lock().unwrap().remove(&id)) else {
+                    continue;
+                };
+                let result = match msg.get("error") {
+                    Some(err) => Err(NeboError::Execution(format!(
+                        "MCP server `{}` returned error {}: {}",
+                        label,
+                        err.get("code").unwrap_or(&Value::Null),
+                        err.get("message").and_then(Value::as_str).unwrap_or_default()
+                    ))),
+                    None => Ok(msg.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+        }
+    }
+}
+
+/// An MCP server tool served as a Nebo tool.
+#[derive(Debug, Clone)]
+pub struct McpTool {
+    client: McpClient,
+    name: String,
+    description: String,
+    schema: Value,
+    requires_approval: bool,
+}
+
+impl McpTool {
+    /// Build a handler from one entry of a `tools/list` result.
+    fn from_definition(client: McpClient, tool: &Value) -> Result<Self, NeboError> {
+        let name = tool
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| NeboError::Execution(format!("MCP tool without a name: {tool}")))?;
+        let annotations = tool.get("annotations");
+        let hint = |key: &str| annotations.and_then(|a| a.get(key)).and_then(Value::as_bool);
+        Ok(Self {
+            client,
+            name: name.to_string(),
+            description: tool
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            schema: tool
+                .get("inputSchema")
+                .cloned()
+                .unwrap_or_else(|| json!({"type": "object"})),
+            // MCP treats a tool that is not read-only as destructive unless it
+            // says otherwise.
+            requires_approval: hint("readOnlyHint") != Some(true)
+                && hint("destructiveHint") != Some(false),
+        })
+    }
+}
+
+#[async_trait]
+impl ToolHandler for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, input: Value, _ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        self.client.call_tool(&self.name, input).await
+    }
+
+    /// Tools that may be destructive ask for approval: anything not marked
+    /// read-only or non-destructive.
+    fn requires_approval(&self) -> bool {
+        self.requires_approval
+    }
+
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        self.client.shutdown().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stub server that answers every request with `ok`, exits when asked
+    /// to call `crash`, and appends a line to `$STARTS` each time it starts.
+    const STUB: &str = r#"
+echo started >> "$STARTS"
+while IFS= read -r line; do
+    id=$(printf '%s\n' "$line" | sed -n 's/.*"id":\([0-9][0-9]*\).*/\1/p')
+    [ -z "$id" ] && continue
+    case "$line" in
+        *'"crash"'*) exit 1 ;;
+    esac
+    printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"ok"}]}}\n' "$id"
+done
+"#;
+
+    /// The stub, with a fresh start counter named after the test.
+    fn stub(test: &str, max_restarts: u32) -> (McpCommand, PathBuf) {
+        let starts = std::env::temp_dir().join(format!("nebo-mcp-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&starts);
+        let command = McpCommand::new("sh")
+            .args(["-c", STUB])
+            .env("STARTS", starts.to_string_lossy())
+            .max_restarts(max_restarts);
+        (command, starts)
+    }
+
+    fn starts(path: &std::path::Path) -> usize {
+        std::fs::read_to_string(path).map_or(0, |s| s.lines().count())
+    }
+
+    async fn crash(client: &McpClient) {
+        let err = client.call_tool("crash", json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("exited before answering"), "{err}");
+    }
+
+    async fn echo(client: &McpClient) -> Result<String, NeboError> {
+        client.call_tool("echo", json!({})).await.map(|out| out.to_text())
+    }
+
+    #[tokio::test]
+    async fn restarts_until_the_limit() {
+        let (command, path) = stub("limit", 2);
+        let client = McpClient::spawn(command).await.unwrap();
+        assert_eq!(echo(&client).await.unwrap(), "ok");
+        for restarted in 2..=3 {
+            crash(&client).await;
+            assert_eq!(echo(&client).await.unwrap(), "ok");
+            assert_eq!(starts(&path), restarted);
+        }
+
+        crash(&client).await;
+        let err = echo(&client).await.unwrap_err();
+        assert!(err.to_string().contains("gave up after 2 restarts"), "{err}");
+        assert_eq!(starts(&path), 3);
+
+        // An explicit shutdown resets the budget.
+        client.shutdown().await;
+        assert_eq!(echo(&client).await.unwrap(), "ok");
+        assert_eq!(starts(&path), 4);
+        client.shutdown().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn stable_servers_get_a_fresh_budget() {
+        let (command, path) = stub("stable", 1);
+        let client = McpClient::spawn(command).await.unwrap();
+        crash(&client).await;
+        assert_eq!(echo(&client).await.unwrap(), "ok");
+        crash(&client).await;
+        assert!(echo(&client).await.is_err());
+
+        // Pretend the server that just exited had been up for a while.
+        {
+            let mut state = client.inner.state.lock().await;
+            let conn = Arc::get_mut(state.conn.as_mut().unwrap()).unwrap();
+            conn.started = Instant::now().checked_sub(STABLE_AFTER).unwrap();
+        }
+        assert_eq!(echo(&client).await.unwrap(), "ok");
+        assert_eq!(starts(&path), 3);
+
+        // A server that exits right away still uses up the budget.
+        crash(&client).await;
+        assert!(echo(&client).await.is_err());
+        assert_eq!(starts(&path), 3);
+        client.shutdown().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    fn tool(annotations: Value) -> McpTool {
+        let client = McpClient {
+            inner: Arc::new(ClientInner {
+                command: McpCommand::new("unused"),
+                state: Default::default(),
+                next_id: AtomicU64::new(1),
+            }),
+        };
+        McpTool::from_definition(client, &json!({"name": "t", "annotations": annotations})).unwrap()
+    }
+
+    #[test]
+    fn approval_follows_mcp_hint_defaults() {
+        assert!(tool(json!({})).requires_approval());
+        assert!(tool(json!({"destructiveHint": true})).requires_approval());
+        assert!(tool(json!({"readOnlyHint": false})).requires_approval());
+        assert!(!tool(json!({"readOnlyHint": true})).requires_approval());
+        assert!(!tool(json!({"readOnlyHint": true, "destructiveHint": true})).requires_approval());
+        assert!(!tool(json!({"destructiveHint": false})).requires_approval());
+        assert!(tool(Value::Null).requires_approval());
+    }
+}
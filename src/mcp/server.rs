@@ -0,0 +1,576 @@
+//! Serve an app's tools over MCP, for [`NeboApp::run_mcp_stdio`](crate::NeboApp::run_mcp_stdio).
+//!
+//! Calls go through the same path as the gRPC `Execute` RPC, so input
+//! validation, timeouts and background jobs behave the same. MCP has no job
+//! polling, so a call that starts a job waits for its result.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use super::{error_response, notification, response, result_from_response};
+use super::{INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION, SUPPORTED_VERSIONS};
+use crate::context::{ProgressSink, ToolContext};
+use crate::error::NeboError;
+use crate::jobs::Jobs;
+use crate::pb;
+use crate::schema::export::{export, Format};
+use crate::tool::ToolBridge;
+
+/// How often a call that started a background job checks for its result.
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+struct Server {
+    bridge: ToolBridge,
+    /// Lines to write to the client.
+    out: mpsc::UnboundedSender<String>,
+    /// In-flight `tools/call` requests by JSON-RPC id.
+    calls: Mutex<HashMap<String, CancellationToken>>,
+}
+
+/// Answer MCP requests read from `input` until it closes or `signal`
+/// resolves, then give in-flight calls up to `drain` to finish. A read error
+/// ends the session the same way and is returned after the drain.
+pub(crate) async fn serve<R, W, F>(
+    bridge: ToolBridge,
+    input: R,
+    output: W,
+    signal: F,
+    drain: Duration,
+) -> Result<(), NeboError>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+    F: Future<Output = ()>,
+{
+    let (out, rx) = mpsc::unbounded_channel();
+    let writer = tokio::spawn(write_lines(output, rx));
+    let app = bridge.env.name.clone();
+    let server = Arc::new(Server {
+        bridge,
+        out,
+        calls: Mutex::default(),
+    });
+
+    let mut lines = BufReader::new(input).lines();
+    let mut tasks = JoinSet::new();
+    let mut read = Ok(());
+    tokio::pin!(signal);
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => server.handle(&line, &mut tasks),
+                Ok(None) => break,
+                Err(e) => {
+                    read = Err(e.into());
+                    break;
+                }
+            },
+            Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+            _ = &mut signal => break,
+        }
+    }
+
+    let drained = tokio::time::timeout(drain, async {
+        while tasks.join_next().await.is_some() {}
+    });
+    if drained.await.is_err() {
+        eprintln!(
+            "[{}] drain timeout of {:?} elapsed, abandoning in-flight calls",
+            app, drain
+        );
+        tasks.shutdown().await;
+    }
+    // Flush what is queued; a tool holding on to its context must not keep
+    // the process alive.
+    drop(server);
+    let _ = tokio::time::timeout(drain, writer).await;
+    read
+}
+
+async fn write_lines<W: AsyncWrite + Unpin>(mut output: W, mut rx: mpsc::UnboundedReceiver<String>) {
+    while let Some(mut line) = rx.recv().await {
+        line.push('\n');
+        if output.write_all(line.as_bytes()).await.is_err() || output.flush().await.is_err() {
+            return;
+        }
+    }
+}
+
+impl Server {
+    fn send(&self, line: String) {
+        let _ = self.out.send(line);
+    }
+
+    fn handle(self: &Arc<Self>, line: &str, tasks: &mut JoinSet<()>) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let msg: Value = match serde_json::from_str(line) {
+            Ok(msg) => msg,
+            Err(e) => {
+                self.send(error_response(&Value::Null, PARSE_ERROR, &e.to_string()));
+                return;
+            }
+        };
+        // Responses to requests we never sent have no method; ignore them.
+        let Some(method) = msg.get("method").and_then(Value::as_str) else {
+            return;
+        };
+        let params = msg.get("params").cloned().unwrap_or_else(|| json!({}));
+        let Some(id) = msg.get("id").cloned() else {
+            if method == "notifications/cancelled" {
+                let key = params.get("requestId").map(Value::to_string).unwrap_or_default();
+                if let Some(cancel) = self.calls.lock().unwrap().remove(&key) {
+                    cancel.cancel();
+                }
+            }
+            return;
+        };
+
+        match method {
+            "initialize" => self.send(response(&id, self.initialize(&params))),
+            "ping" => self.send(response(&id, json!({}))),
+            "tools/list" => self.send(response(&id, self.list_tools())),
+            "tools/call" => self.call(id, &params, tasks),
+            _ => self.send(error_response(
+                &id,
+                METHOD_NOT_FOUND,
+                &format!("method `{method}` is not supported"),
+            )),
+        }
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = SUPPORTED_VERSIONS
+            .into_iter()
+            .find(|v| Some(*v) == requested)
+            .unwrap_or(PROTOCOL_VERSION);
+        let env = &self.bridge.env;
+        let or = |s: &str, default: &str| if s.is_empty() { default.to_string() } else { s.to_string() };
+        json!({
+            "protocolVersion": version,
+            "capabilities": {"tools": {"listChanged": false}},
+            "serverInfo": {"name": or(&env.name, "nebo-app"), "version": or(&env.version, "0.0.0")},
+        })
+    }
+
+    /// Tools that require approval are marked destructive, which MCP hosts
+    /// use to ask the user before calling them.
+    fn list_tools(&self) -> Value {
+        let tools: Vec<Value> = self
+            .bridge
+            .tools
+            .iter()
+            .map(|tool| {
+                let mut def = export(tool.as_ref(), Format::Mcp);
+                if tool.requires_approval() {
+                    def["annotations"] = json!({"readOnlyHint": false, "destructiveHint": true});
+                }
+                def
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    fn call(self: &Arc<Self>, id: Value, params: &Value, tasks: &mut JoinSet<()>) {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+        if self.bridge.tools.get(name).is_none() {
+            self.send(error_response(&id, INVALID_PARAMS, &format!("unknown tool: {name}")));
+            return;
+        }
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+        let req = pb::ExecuteRequest {
+            input: serde_json::to_vec(&arguments).unwrap_or_default(),
+            tool_name: name.to_string(),
+            ..Default::default()
+        };
+        let progress_token = params.get("_meta").and_then(|m| m.get("progressToken")).cloned();
+
+        let key = id.to_string();
+        let cancel = CancellationToken::new();
+        self.calls.lock().unwrap().insert(key.clone(), cancel.clone());
+        let server = self.clone();
+        tasks.spawn(async move {
+            // A cancelled request gets no response.
+            let resp = tokio::select! {
+                resp = server.execute(req, progress_token) => Some(resp),
+                _ = cancel.cancelled() => None,
+            };
+            server.calls.lock().unwrap().remove(&key);
+            if let Some(resp) = resp {
+                server.send(response(&id, result_from_response(resp)));
+            }
+        });
+    }
+
+    async fn execute(&self, req: pb::ExecuteRequest, progress_token: Option<Value>) -> pb::ExecuteResponse {
+        let jobs = self.bridge.jobs.clone();
+        let mut ctx = ToolContext::new(self.bridge.env.clone(), None).with_jobs(jobs.clone());
+        let (tx, mut events) = mpsc::unbounded_channel();
+        if progress_token.is_some() {
+            ctx.progress = ProgressSink::new(tx);
+        }
+        let forward = |event: pb::ExecuteEvent| {
+            if let (Some(token), Some(pb::execute_event::Event::Progress(p))) = (&progress_token, event.event) {
+                let params = json!({
+                    "progressToken": token,
+                    "progress": p.percent,
+                    "total": 100,
+                    "message": p.message,
+                });
+                self.send(notification("notifications/progress", params));
+            }
+        };
+
+        // Forward progress as it happens, and all of it before the result.
+        let run = self.bridge.run(req, ctx);
+        tokio::pin!(run);
+        let resp = loop {
+            tokio::select! {
+                biased;
+                Some(Ok(event)) = events.recv() => forward(event),
+                resp = &mut run => break resp,
+            }
+        };
+        while let Ok(Ok(event)) = events.try_recv() {
+            forward(event);
+        }
+
+        if resp.job_id.is_empty() {
+            return resp;
+        }
+        let _cancel_job = CancelJob {
+            jobs: &jobs,
+            id: resp.job_id.clone(),
+        };
+        loop {
+            match jobs.result(&resp.job_id) {
+                Some(Some(result)) => return result,
+                Some(None) => tokio::time::sleep(JOB_POLL_INTERVAL).await,
+                None => return resp,
+            }
+        }
+    }
+}
+
+/// Cancels a background job whose MCP caller went away before it finished.
+struct CancelJob<'a> {
+    jobs: &'a Jobs,
+    id: String,
+}
+
+impl Drop for CancelJob<'_> {
+    fn drop(&mut self) {
+        self.jobs.cancel(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::AppEnv;
+    use crate::output::ToolOutput;
+    use crate::tool::ToolHandler;
+    use crate::{tool_fn, NeboApp};
+    use async_trait::async_trait;
+    use tokio::io::{DuplexStream, Lines};
+    use tokio::sync::{oneshot, Notify};
+    use tokio::task::JoinHandle;
+
+    /// Requires approval and records its lifecycle hooks.
+    struct Delete {
+        hooks: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl ToolHandler for Delete {
+        fn name(&self) -> &str {
+            "delete"
+        }
+        fn description(&self) -> &str {
+            "Delete a file."
+        }
+        fn schema(&self) -> Value {
+            json!({"type": "object", "properties": {"path": {"type": "string"}}})
+        }
+        fn requires_approval(&self) -> bool {
+            true
+        }
+        async fn execute(&self, input: Value, _ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+            Ok(format!("deleted {}", input["path"].as_str().unwrap_or_default()).into())
+        }
+        async fn on_start(&self) -> Result<(), NeboError> {
+            self.hooks.lock().unwrap().push("on_start");
+            Ok(())
+        }
+        async fn on_shutdown(&self) -> Result<(), NeboError> {
+            self.hooks.lock().unwrap().push("on_shutdown");
+            Ok(())
+        }
+    }
+
+    /// A client talking to an app served over in-memory pipes.
+    struct Session {
+        input: DuplexStream,
+        output: Lines<BufReader<DuplexStream>>,
+        stop: Option<oneshot::Sender<()>>,
+        done: JoinHandle<Result<(), NeboError>>,
+    }
+
+    impl Session {
+        fn start(app: NeboApp) -> Self {
+            let (input, server_input) = tokio::io::duplex(1 << 16);
+            let (server_output, output) = tokio::io::duplex(1 << 16);
+            let (stop, stopped) = oneshot::channel::<()>();
+            let signal = async {
+                let _ = stopped.await;
+            };
+            let done = tokio::spawn(app.run_mcp(server_input, server_output, signal));
+            Self {
+                input,
+                output: BufReader::new(output).lines(),
+                stop: Some(stop),
+                done,
+            }
+        }
+
+        async fn send(&mut self, msg: Value) {
+            self.input.write_all(format!("{msg}\n").as_bytes()).await.unwrap();
+        }
+
+        async fn recv(&mut self) -> Value {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.output.next_line())
+                .await
+                .expect("no message from the server")
+                .unwrap()
+                .expect("server closed its output");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// Send a request without waiting for its response.
+        async fn begin(&mut self, id: u64, method: &str, params: Value) {
+            self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+                .await;
+        }
+
+        async fn notify(&mut self, method: &str, params: Value) {
+            self.send(json!({"jsonrpc": "2.0", "method": method, "params": params})).await;
+        }
+
+        async fn request(&mut self, id: u64, method: &str, params: Value) -> Value {
+            self.begin(id, method, params).await;
+            let msg = self.recv().await;
+            assert_eq!(msg["id"], id, "{msg}");
+            msg
+        }
+
+        async fn call(&mut self, id: u64, name: &str, arguments: Value) -> Value {
+            self.request(id, "tools/call", json!({"name": name, "arguments": arguments}))
+                .await
+        }
+
+        /// Stop the server as SIGTERM would and wait for it to finish.
+        async fn stop(mut self) -> Result<(), NeboError> {
+            let _ = self.stop.take().unwrap().send(());
+            self.finish().await
+        }
+
+        async fn finish(self) -> Result<(), NeboError> {
+            tokio::time::timeout(Duration::from_secs(5), self.done)
+                .await
+                .expect("server did not stop")
+                .unwrap()
+        }
+    }
+
+    fn app() -> NeboApp {
+        let env = AppEnv {
+            name: "files".into(),
+            version: "1.2.0".into(),
+            ..AppEnv::default()
+        };
+        let echo = tool_fn("echo", "Echo the text.", json!({"type": "object"}), |input, _ctx| {
+            async move { Ok(input["text"].as_str().unwrap_or_default().to_string()) }
+        });
+        let fail = tool_fn("fail", "Always fails.", json!({"type": "object"}), |_, _ctx| {
+            async move { Err::<String, _>(NeboError::Execution("disk full".into())) }
+        });
+        NeboApp::with_env(env)
+            .drain_timeout(Duration::from_secs(2))
+            .register_tool(echo)
+            .register_tool(fail)
+    }
+
+    fn text(result: &Value) -> &str {
+        result["content"][0]["text"].as_str().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_the_protocol_version() {
+        let mut session = Session::start(app());
+        let init = session
+            .request(1, "initialize", json!({"protocolVersion": "2025-03-26"}))
+            .await;
+        assert_eq!(
+            init["result"],
+            json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": {"tools": {"listChanged": false}},
+                "serverInfo": {"name": "files", "version": "1.2.0"},
+            })
+        );
+        let init = session.request(2, "initialize", json!({"protocolVersion": "1999-01-01"})).await;
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        let unknown = session.request(3, "resources/list", json!({})).await;
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lists_tools_and_marks_approval_as_destructive() {
+        let hooks = Arc::new(Mutex::new(Vec::new()));
+        let app = app().register_tool(Delete { hooks: hooks.clone() });
+        let mut session = Session::start(app);
+        let list = session.request(1, "tools/list", json!({})).await;
+        let tools = list["result"]["tools"].as_array().unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["echo", "fail", "delete"]);
+        assert_eq!(tools[0]["inputSchema"], json!({"type": "object"}));
+        assert!(tools[0].get("annotations").is_none());
+        assert_eq!(
+            tools[2]["annotations"],
+            json!({"readOnlyHint": false, "destructiveHint": true})
+        );
+        drop(session.input);
+        session.done.await.unwrap().unwrap();
+        assert_eq!(*hooks.lock().unwrap(), ["on_start", "on_shutdown"]);
+    }
+
+    #[tokio::test]
+    async fn calls_tools() {
+        let mut session = Session::start(app());
+        let ok = session.call(1, "echo", json!({"text": "hello"})).await;
+        assert_eq!(ok["result"]["isError"], false);
+        assert_eq!(text(&ok["result"]), "hello");
+
+        let failed = session.call(2, "fail", json!({})).await;
+        assert_eq!(failed["result"]["isError"], true);
+        assert!(text(&failed["result"]).contains("disk full"), "{failed}");
+
+        let unknown = session.call(3, "missing", json!({})).await;
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+        assert_eq!(unknown["error"]["message"], "unknown tool: missing");
+
+        session.send(json!("not a request")).await;
+        session.input.write_all(b"{oops\n").await.unwrap();
+        assert_eq!(session.recv().await["error"]["code"], PARSE_ERROR);
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_calls_stop_without_a_response() {
+        let started = Arc::new(Notify::new());
+        let cancelled = Arc::new(Notify::new());
+        let (s, c) = (started.clone(), cancelled.clone());
+        let wait = tool_fn("wait", "Waits forever.", json!({"type": "object"}), move |_, ctx| {
+            let (started, cancelled) = (s.clone(), c.clone());
+            async move {
+                let token = ctx.cancel.clone();
+                tokio::spawn(async move {
+                    token.cancelled().await;
+                    cancelled.notify_one();
+                });
+                started.notify_one();
+                std::future::pending::<Result<String, NeboError>>().await
+            }
+        });
+        let mut session = Session::start(app().register_tool(wait));
+        session.begin(7, "tools/call", json!({"name": "wait"})).await;
+        started.notified().await;
+        session.notify("notifications/cancelled", json!({"requestId": 7})).await;
+        tokio::time::timeout(Duration::from_secs(5), cancelled.notified())
+            .await
+            .expect("the tool was not cancelled");
+        // The next message is the ping's response: the call got none.
+        session.request(8, "ping", json!({})).await;
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn forwards_progress_before_the_result() {
+        let report = tool_fn("report", "Reports progress.", json!({"type": "object"}), |_, ctx| {
+            async move {
+                ctx.progress.progress(50.0, "halfway");
+                Ok("done")
+            }
+        });
+        let mut session = Session::start(app().register_tool(report));
+        let params = json!({"name": "report", "_meta": {"progressToken": "tok-1"}});
+        session.begin(1, "tools/call", params).await;
+        let progress = session.recv().await;
+        assert_eq!(progress["method"], "notifications/progress");
+        assert_eq!(
+            progress["params"],
+            json!({"progressToken": "tok-1", "progress": 50.0, "total": 100, "message": "halfway"})
+        );
+        let result = session.recv().await;
+        assert_eq!(result["id"], 1);
+        assert_eq!(text(&result["result"]), "done");
+
+        // Without a token, nothing but the result is sent.
+        let result = session.call(2, "report", json!({})).await;
+        assert_eq!(text(&result["result"]), "done");
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_background_jobs() {
+        let report = tool_fn("report", "Builds a report.", json!({"type": "object"}), |_, ctx| {
+            async move {
+                Ok(ctx.spawn_job(|job| async move {
+                    job.progress(10.0, "started");
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(ToolOutput::text("report ready"))
+                }))
+            }
+        });
+        let mut session = Session::start(app().register_tool(report));
+        let result = session.call(1, "report", json!({})).await;
+        assert_eq!(result["result"]["isError"], false);
+        assert_eq!(text(&result["result"]), "report ready");
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_errors_drain_in_flight_calls() {
+        let slow = tool_fn("slow", "Takes a while.", json!({"type": "object"}), |_, _ctx| {
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok("finished")
+            }
+        });
+        let mut session = Session::start(app().register_tool(slow));
+        session.begin(1, "tools/call", json!({"name": "slow"})).await;
+        session.input.write_all(b"\xff\xfe\n").await.unwrap();
+        let result = session.recv().await;
+        assert_eq!(result["id"], 1);
+        assert_eq!(text(&result["result"]), "finished");
+        assert!(matches!(session.finish().await, Err(NeboError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn needs_a_tool() {
+        let (input, output) = tokio::io::duplex(64);
+        let app = NeboApp::with_env(AppEnv::default());
+        let result = app.run_mcp(input, output, std::future::pending()).await;
+        assert!(matches!(result, Err(NeboError::NoHandlers)));
+    }
+}
@@ -0,0 +1,627 @@
+//! Tools generated from OpenAPI 3 specifications.
+//!
+//! [`OpenApiTool`] turns the operations of a spec into STRAP actions: each
+//! `operationId` becomes an action, the operation's path, query and header
+//! parameters become properties, and a JSON request body becomes `body`.
+//! A parameter whose name clashes with `action`, `body` or another parameter
+//! of the same operation is prefixed with its location, e.g. `header_id`.
+//! Executing an action performs the HTTP request and returns the response.
+//!
+//! ```no_run
+//! # use nebo_sdk::openapi::OpenApiTool;
+//! # async fn run() -> Result<(), nebo_sdk::NeboError> {
+//! let app = nebo_sdk::NeboApp::new()?;
+//! let billing = OpenApiTool::load(app.env(), "billing", "openapi.yaml")?
+//!     .operations(&["listInvoices", "getInvoice"])
+//!     .forward_user_token(true);
+//! app.register_tool(billing).run().await
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use reqwest::{Method, Url};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::approval::Approval;
+use crate::context::ToolContext;
+use crate::env::AppEnv;
+use crate::error::NeboError;
+use crate::output::ToolOutput;
+use crate::schema::{strap_schema, Prop, SchemaBuilder};
+use crate::tool::ToolHandler;
+
+const METHODS: [&str; 7] = ["get", "put", "post", "delete", "patch", "head", "options"];
+/// Limit on chains of `$ref`s that point at other `$ref`s.
+const MAX_REF_DEPTH: usize = 32;
+/// Longest error response body quoted back to the model.
+const MAX_ERROR_BODY: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Path,
+    Query,
+    Header,
+}
+
+impl Location {
+    fn as_str(self) -> &'static str {
+        match self {
+            Location::Path => "path",
+            Location::Query => "query",
+            Location::Header => "header",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Param {
+    name: String,
+    /// The input property carrying the value; `name` unless that clashes.
+    property: String,
+    location: Location,
+    required: bool,
+    schema: Value,
+}
+
+/// One operation of the spec, served as an action.
+#[derive(Debug, Clone)]
+struct Operation {
+    id: String,
+    method: Method,
+    path: String,
+    summary: String,
+    params: Vec<Param>,
+    /// JSON request body schema and whether the body is required.
+    body: Option<(Value, bool)>,
+}
+
+/// A tool whose actions are the operations of an OpenAPI 3 spec.
+///
+/// By default every operation is exposed and requests go to the spec's first
+/// server. Responses are returned as JSON when they parse as JSON, as text
+/// otherwise; non-2xx responses are reported as errors with the status and
+/// body.
+#[derive(Debug, Clone)]
+pub struct OpenApiTool {
+    name: String,
+    description: String,
+    custom_description: bool,
+    operations: Vec<Operation>,
+    /// Operations named by [`OpenApiTool::operations`] that the spec lacks.
+    missing: Vec<String>,
+    schema: Value,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    forward_token: bool,
+    approve_writes: bool,
+    client: reqwest::Client,
+}
+
+impl OpenApiTool {
+    /// Load a JSON or YAML spec from `path`, relative to the app directory.
+    pub fn load(env: &AppEnv, name: &str, path: impl AsRef<Path>) -> Result<Self, NeboError> {
+        let path = Path::new(&env.dir).join(path);
+        let invalid = |e: &dyn std::fmt::Display| {
+            NeboError::Other(format!("invalid OpenAPI spec {}: {}", path.display(), e))
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| invalid(&e))?;
+        let spec = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&text).map_err(|e| invalid(&e))?
+        } else {
+            let yaml: serde_norway::Value = serde_norway::from_str(&text).map_err(|e| invalid(&e))?;
+            yaml_to_json(yaml)
+        };
+        Self::from_spec(name, spec).map_err(|e| invalid(&e))
+    }
+
+    /// Build a tool from a parsed spec.
+    pub fn from_spec(name: &str, spec: Value) -> Result<Self, NeboError> {
+        let version = spec.get("openapi").and_then(Value::as_str).unwrap_or_default();
+        if !version.starts_with("3.") {
+            return Err(NeboError::Other(format!(
+                "only OpenAPI 3 is supported, found {}",
+                spec.get("openapi").or(spec.get("swagger")).unwrap_or(&Value::Null)
+            )));
+        }
+        let operations = parse_operations(&spec)?;
+        if operations.is_empty() {
+            return Err(NeboError::Other("spec has no operations".into()));
+        }
+        let mut tool = Self {
+            name: name.to_string(),
+            description: String::new(),
+            custom_description: false,
+            operations,
+            missing: Vec::new(),
+            schema: Value::Null,
+            base_url: server_url(&spec),
+            headers: Vec::new(),
+            forward_token: false,
+            approve_writes: false,
+            client: reqwest::Client::new(),
+        };
+        tool.description = describe(&spec, &tool.operations);
+        tool.schema = build_schema(&tool.operations);
+        Ok(tool)
+    }
+
+    /// Expose only these operations, by `operationId`, in this order. Names
+    /// the spec lacks make [`on_start`](ToolHandler::on_start) fail.
+    pub fn operations(mut self, ids: &[&str]) -> Self {
+        let mut selected = Vec::new();
+        for id in ids {
+            match self.operations.iter().find(|op| op.id == *id) {
+                Some(op) => selected.push(op.clone()),
+                None => self.missing.push(id.to_string()),
+            }
+        }
+        self.operations = selected;
+        self.schema = build_schema(&self.operations);
+        if !self.custom_description {
+            self.description = describe_actions(&self.description, &self.operations);
+        }
+        self
+    }
+
+    /// Replace the generated description (spec title plus the action list).
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self.custom_description = true;
+        self
+    }
+
+    /// Send requests here instead of the spec's first server, e.g. a local
+    /// mock server in tests.
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Send a header with every request, e.g. an API key.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send the user's NeboLoop JWT ([`ToolContext::token`]) as a bearer
+    /// token. Nebo only provides the token to apps that declare the
+    /// `user:token` permission; without it no header is sent.
+    pub fn forward_user_token(mut self, forward: bool) -> Self {
+        self.forward_token = forward;
+        self
+    }
+
+    /// Ask the user before any operation other than `GET`, `HEAD` or
+    /// `OPTIONS`.
+    pub fn approve_writes(mut self, approve: bool) -> Self {
+        self.approve_writes = approve;
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with timeouts or a proxy.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn operation(&self, input: &Value) -> Result<&Operation, NeboError> {
+        let action = input.get("action").and_then(Value::as_str).unwrap_or_default();
+        self.operations
+            .iter()
+            .find(|op| op.id == action)
+            .ok_or_else(|| NeboError::Execution(format!("unknown action: {action}")))
+    }
+
+    fn url(&self, op: &Operation, input: &Value) -> Result<Url, NeboError> {
+        let base = self.base_url.as_deref().ok_or_else(|| {
+            NeboError::Execution("the spec has no absolute server URL; set one with `base_url`".into())
+        })?;
+        let mut url = Url::parse(base)
+            .map_err(|e| NeboError::Execution(format!("invalid base URL {base:?}: {e}")))?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| NeboError::Execution(format!("base URL {base:?} cannot have a path")))?;
+            segments.pop_if_empty();
+            for segment in op.path.split('/').filter(|s| !s.is_empty()) {
+                let mut segment = segment.to_string();
+                for p in op.params.iter().filter(|p| p.location == Location::Path) {
+                    let value = input.get(&p.property).map(param_string).unwrap_or_default();
+                    segment = segment.replace(&format!("{{{}}}", p.name), &value);
+                }
+                segments.push(&segment);
+            }
+        }
+        {
+            let mut query = url.query_pairs_mut();
+            for p in op.params.iter().filter(|p| p.location == Location::Query) {
+                match input.get(&p.property) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            query.append_pair(&p.name, &param_string(item));
+                        }
+                    }
+                    Some(value) => {
+                        query.append_pair(&p.name, &param_string(value));
+                    }
+                }
+            }
+        }
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl ToolHandler for OpenApiTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let op = self.operation(&input)?;
+        let mut req = self.client.request(op.method.clone(), self.url(op, &input)?);
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        for p in op.params.iter().filter(|p| p.location == Location::Header) {
+            if let Some(value) = input.get(&p.property).filter(|v| !v.is_null()) {
+                req = req.header(&p.name, param_string(value));
+            }
+        }
+        if let (true, Some(token)) = (self.forward_token, &ctx.token) {
+            req = req.bearer_auth(token);
+        }
+        if let (Some(_), Some(body)) = (&op.body, input.get("body")) {
+            req = req.json(body);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| NeboError::Execution(format!("{} {} failed: {}", op.method, op.path, e)))?;
+        let status = resp.status();
+        let mime_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| NeboError::Execution(format!("{} {} failed: {}", op.method, op.path, e)))?;
+
+        if !status.is_success() {
+            let body = String::from_utf8_lossy(&bytes);
+            let body: String = body.chars().take(MAX_ERROR_BODY).collect();
+            return Err(NeboError::Execution(format!(
+                "{} {} returned {}: {}",
+                op.method, op.path, status, body
+            )));
+        }
+        let output = if let Ok(json) = serde_json::from_slice::<Value>(&bytes) {
+            ToolOutput::json(json)
+        } else if let Ok(text) = std::str::from_utf8(&bytes) {
+            ToolOutput::text(text)
+        } else {
+            ToolOutput::new().with_file_data("response", &mime_type, bytes.to_vec())
+        };
+        Ok(output.with_metadata("http_status", status.as_str()))
+    }
+
+    fn requires_approval(&self) -> bool {
+        self.approve_writes && self.operations.iter().any(|op| !is_safe(&op.method))
+    }
+
+    async fn approval(&self, input: &Value, _ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let op = self.operation(input)?;
+        if !self.approve_writes || is_safe(&op.method) {
+            return Ok(Approval::not_required());
+        }
+        let prompt = if op.summary.is_empty() {
+            format!("{} {}", op.method, op.path)
+        } else {
+            format!("{} ({} {})", op.summary, op.method, op.path)
+        };
+        Ok(Approval::required(prompt))
+    }
+
+    async fn on_start(&self) -> Result<(), NeboError> {
+        if self.missing.is_empty() {
+            return Ok(());
+        }
+        Err(NeboError::Other(format!(
+            "tool `{}`: operations not found in the OpenAPI spec: {}",
+            self.name,
+            self.missing.join(", ")
+        )))
+    }
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Every operation in the spec, in document order.
+fn parse_operations(spec: &Value) -> Result<Vec<Operation>, NeboError> {
+    let mut out = Vec::new();
+    let paths = spec.get("paths").and_then(Value::as_object);
+    for (path, item) in paths.into_iter().flatten() {
+        let item = deref(spec, item, 0);
+        let shared = item.get("parameters").and_then(Value::as_array);
+        for method in METHODS {
+            let Some(op) = item.get(method) else {
+                continue;
+            };
+            // Operation-level parameters override path-level ones.
+            let mut params: Vec<Param> = Vec::new();
+            let own = op.get("parameters").and_then(Value::as_array);
+            for p in own.into_iter().flatten().chain(shared.into_iter().flatten()) {
+                let Some(p) = parse_param(spec, p) else {
+                    continue;
+                };
+                if !params.iter().any(|q| q.name == p.name && q.location == p.location) {
+                    params.push(p);
+                }
+            }
+            let body = op.get("requestBody").map(|b| deref(spec, b, 0)).and_then(|b| {
+                let schema = b.pointer("/content/application~1json/schema")?;
+                let required = b.get("required").and_then(Value::as_bool).unwrap_or(false);
+                Some((resolve(spec, schema, &mut Vec::new()), required))
+            });
+            let id = match op.get("operationId").and_then(Value::as_str) {
+                Some(id) => id.to_string(),
+                None => derived_id(method, path),
+            };
+            name_properties(&id, &mut params, body.is_some())?;
+            let summary = op
+                .get("summary")
+                .or(op.get("description"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            out.push(Operation {
+                id,
+                method: Method::from_bytes(method.to_uppercase().as_bytes()).unwrap_or(Method::GET),
+                path: path.clone(),
+                summary: summary.lines().next().unwrap_or_default().to_string(),
+                params,
+                body,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Pick each parameter's input property. A name taken by the STRAP `action`
+/// field, the request body or another parameter of the operation would make
+/// one value stand for two, so such parameters are prefixed with their
+/// location instead.
+fn name_properties(op: &str, params: &mut [Param], has_body: bool) -> Result<(), NeboError> {
+    let clashes = |name: &str, params: &[Param]| {
+        name == "action"
+            || (has_body && name == "body")
+            || params.iter().filter(|p| p.name == name).count() > 1
+    };
+    let names: Vec<String> = params
+        .iter()
+        .map(|p| match clashes(&p.name, params) {
+            true => format!("{}_{}", p.location.as_str(), p.name),
+            false => p.name.clone(),
+        })
+        .collect();
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            return Err(NeboError::Other(format!(
+                "operation `{op}` has more than one input named `{name}`"
+            )));
+        }
+    }
+    for (p, name) in params.iter_mut().zip(names) {
+        p.property = name;
+    }
+    Ok(())
+}
+
+fn parse_param(spec: &Value, p: &Value) -> Option<Param> {
+    let p = deref(spec, p, 0);
+    let location = match p.get("in").and_then(Value::as_str)? {
+        "path" => Location::Path,
+        "query" => Location::Query,
+        "header" => Location::Header,
+        _ => return None,
+    };
+    let mut schema = p.get("schema").map(|s| resolve(spec, s, &mut Vec::new())).unwrap_or_else(|| json!({}));
+    if let (Some(desc), Some(obj)) = (p.get("description"), schema.as_object_mut()) {
+        obj.entry("description").or_insert_with(|| desc.clone());
+    }
+    let name = p.get("name").and_then(Value::as_str)?.to_string();
+    Some(Param {
+        property: name.clone(),
+        name,
+        required: location == Location::Path
+            || p.get("required").and_then(Value::as_bool).unwrap_or(false),
+        location,
+        schema,
+    })
+}
+
+/// `get /users/{id}/posts` -> `get_users_id_posts`.
+fn derived_id(method: &str, path: &str) -> String {
+    let slug: Vec<&str> = path
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect();
+    format!("{}_{}", method, slug.join("_"))
+}
+
+/// The STRAP schema: one action per operation, each requiring its required
+/// parameters and body. Operations usually declare `body`, and often
+/// parameters such as `id`, differently; `strap_schema` checks those against
+/// the selected operation's schema rather than the first one declared.
+fn build_schema(operations: &[Operation]) -> Value {
+    let actions = operations
+        .iter()
+        .map(|op| {
+            let mut params = SchemaBuilder::new(&[]);
+            for p in &op.params {
+                params = params.property(&p.property, Prop::from_schema(p.schema.clone()), p.required);
+            }
+            if let Some((schema, required)) = &op.body {
+                params = params.property("body", Prop::from_schema(schema.clone()), *required);
+            }
+            (op.id.clone(), params.build())
+        })
+        .collect();
+    strap_schema("action", actions)
+}
+
+/// `<title>: <description>` followed by the action list.
+fn describe(spec: &Value, operations: &[Operation]) -> String {
+    let info = spec.get("info");
+    let field = |key: &str| info.and_then(|i| i.get(key)).and_then(Value::as_str).unwrap_or_default();
+    let summary = field("description").lines().next().unwrap_or_default();
+    let intro = match (field("title"), summary) {
+        ("", "") => "Calls an HTTP API.".to_string(),
+        (title, "") => format!("Calls the {title} API."),
+        ("", summary) => summary.to_string(),
+        (title, summary) => format!("{title}: {summary}"),
+    };
+    describe_actions(&intro, operations)
+}
+
+/// Replace the action list at the end of a generated description.
+fn describe_actions(description: &str, operations: &[Operation]) -> String {
+    let intro = description.split("\nActions:").next().unwrap_or_default();
+    let actions: Vec<String> = operations
+        .iter()
+        .map(|op| match op.summary.as_str() {
+            "" => format!("- {}: {} {}", op.id, op.method, op.path),
+            summary => format!("- {}: {}", op.id, summary),
+        })
+        .collect();
+    format!("{}\nActions:\n{}", intro, actions.join("\n"))
+}
+
+/// The first server's URL with variables set to their defaults, if absolute.
+fn server_url(spec: &Value) -> Option<String> {
+    let server = spec.get("servers")?.get(0)?;
+    let mut url = server.get("url")?.as_str()?.to_string();
+    let vars = server.get("variables").and_then(Value::as_object);
+    for (name, var) in vars.into_iter().flatten() {
+        let default = var.get("default").and_then(Value::as_str).unwrap_or_default();
+        url = url.replace(&format!("{{{name}}}"), default);
+    }
+    Url::parse(&url).ok()?;
+    Some(url.trim_end_matches('/').to_string())
+}
+
+/// Follow a local `$ref` (`#/components/...`) to its target.
+fn deref<'a>(spec: &'a Value, v: &'a Value, depth: usize) -> &'a Value {
+    match v.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix('#')) {
+        Some(pointer) if depth < MAX_REF_DEPTH => match spec.pointer(pointer) {
+            Some(target) => deref(spec, target, depth + 1),
+            None => v,
+        },
+        _ => v,
+    }
+}
+
+/// Inline every `$ref` in a schema and convert OpenAPI-only keywords to
+/// JSON Schema: `nullable` becomes a `null` type and `example` becomes
+/// `examples`. A recursive reference is replaced by an unconstrained schema.
+fn resolve(spec: &Value, schema: &Value, expanding: &mut Vec<String>) -> Value {
+    let reference = schema.get("$ref").and_then(Value::as_str).map(String::from);
+    if let Some(r) = &reference {
+        if expanding.contains(r) {
+            return json!({});
+        }
+        expanding.push(r.clone());
+    }
+    let resolved = match deref(spec, schema, 0) {
+        Value::Object(obj) => {
+            let mut out = Map::new();
+            for (key, value) in obj {
+                match key.as_str() {
+                    "nullable" | "example" | "xml" | "discriminator" | "externalDocs" | "readOnly"
+                    | "writeOnly" | "deprecated" => {}
+                    "properties" | "patternProperties" => {
+                        let props = value.as_object().into_iter().flatten();
+                        let props = props.map(|(k, v)| (k.clone(), resolve(spec, v, expanding)));
+                        out.insert(key.clone(), Value::Object(props.collect()));
+                    }
+                    "items" | "additionalProperties" | "not" if value.is_object() => {
+                        out.insert(key.clone(), resolve(spec, value, expanding));
+                    }
+                    "allOf" | "anyOf" | "oneOf" => {
+                        let items = value.as_array().into_iter().flatten();
+                        let items = items.map(|v| resolve(spec, v, expanding));
+                        out.insert(key.clone(), Value::Array(items.collect()));
+                    }
+                    _ => {
+                        out.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            if obj.get("nullable").and_then(Value::as_bool) == Some(true) {
+                if let Some(Value::String(t)) = out.get("type") {
+                    let t = t.clone();
+                    out.insert("type".to_string(), json!([t, "null"]));
+                }
+            }
+            if let Some(example) = obj.get("example") {
+                out.entry("examples").or_insert_with(|| json!([example]));
+            }
+            Value::Object(out)
+        }
+        other => other.clone(),
+    };
+    if reference.is_some() {
+        expanding.pop();
+    }
+    resolved
+}
+
+/// Render a parameter value for a path segment, query string or header.
+fn param_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(param_string).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+/// YAML allows non-string keys such as `200:` under `responses`; JSON does not.
+fn yaml_to_json(v: serde_norway::Value) -> Value {
+    match v {
+        serde_norway::Value::Null => Value::Null,
+        serde_norway::Value::Bool(b) => Value::Bool(b),
+        serde_norway::Value::Number(n) => serde_json::to_value(n).unwrap_or(Value::Null),
+        serde_norway::Value::String(s) => Value::String(s),
+        serde_norway::Value::Sequence(items) => Value::Array(items.into_iter().map(yaml_to_json).collect()),
+        serde_norway::Value::Mapping(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let key = match k {
+                        serde_norway::Value::String(s) => s,
+                        other => serde_norway::to_string(&other).unwrap_or_default().trim().to_string(),
+                    };
+                    (key, yaml_to_json(v))
+                })
+                .collect(),
+        ),
+        serde_norway::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
@@ -0,0 +1,201 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::pb;
+
+/// One block of a tool result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentBlock {
+    Text(String),
+    Json(Value),
+    Image {
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    /// A file, either inline (`data`) or by reference (`uri`).
+    File {
+        name: String,
+        mime_type: String,
+        uri: String,
+        data: Vec<u8>,
+    },
+    Citation {
+        title: String,
+        url: String,
+    },
+}
+
+/// The result of a tool execution.
+///
+/// Converts from `String`, `&str` and `serde_json::Value`, so simple tools can
+/// return `Ok(text.into())`. Hosts that only read the legacy `content` field
+/// receive [`ToolOutput::to_text`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolOutput {
+    pub content: Vec<ContentBlock>,
+    pub metadata: HashMap<String, String>,
+    /// Background job started by this call; see [`JobHandle`](crate::jobs::JobHandle).
+    pub job_id: Option<String>,
+}
+
+impl ToolOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new().with_text(text)
+    }
+
+    pub fn json(value: Value) -> Self {
+        Self::new().with_json(value)
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.content.push(ContentBlock::Text(text.into()));
+        self
+    }
+
+    pub fn with_json(mut self, value: Value) -> Self {
+        self.content.push(ContentBlock::Json(value));
+        self
+    }
+
+    pub fn with_image(mut self, mime_type: &str, data: Vec<u8>) -> Self {
+        self.content.push(ContentBlock::Image {
+            mime_type: mime_type.to_string(),
+            data,
+        });
+        self
+    }
+
+    /// Attach a file by reference.
+    pub fn with_file(mut self, name: &str, mime_type: &str, uri: &str) -> Self {
+        self.content.push(ContentBlock::File {
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            uri: uri.to_string(),
+            data: Vec::new(),
+        });
+        self
+    }
+
+    /// Attach a file inline.
+    pub fn with_file_data(mut self, name: &str, mime_type: &str, data: Vec<u8>) -> Self {
+        self.content.push(ContentBlock::File {
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            uri: String::new(),
+            data,
+        });
+        self
+    }
+
+    pub fn with_citation(mut self, title: &str, url: &str) -> Self {
+        self.content.push(ContentBlock::Citation {
+            title: title.to_string(),
+            url: url.to_string(),
+        });
+        self
+    }
+
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Render the output as plain text for hosts without structured content support.
+    pub fn to_text(&self) -> String {
+        let mut citations = 0;
+        self.content
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text(t) => t.clone(),
+                ContentBlock::Json(v) => serde_json::to_string_pretty(v).unwrap_or_default(),
+                ContentBlock::Image { mime_type, data } => {
+                    format!("[image: {}, {} bytes]", mime_type, data.len())
+                }
+                ContentBlock::File { name, uri, .. } if !uri.is_empty() => {
+                    format!("[file: {} ({})]", name, uri)
+                }
+                ContentBlock::File { name, data, .. } => {
+                    format!("[file: {}, {} bytes]", name, data.len())
+                }
+                ContentBlock::Citation { title, url } => {
+                    citations += 1;
+                    format!("[{}] {} - {}", citations, title, url)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub(crate) fn into_response(self) -> pb::ExecuteResponse {
+        pb::ExecuteResponse {
+            content: self.to_text(),
+            blocks: self.content.into_iter().map(to_proto_block).collect(),
+            metadata: self.metadata,
+            job_id: self.job_id.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<String> for ToolOutput {
+    fn from(s: String) -> Self {
+        Self::text(s)
+    }
+}
+
+impl From<&str> for ToolOutput {
+    fn from(s: &str) -> Self {
+        Self::text(s)
+    }
+}
+
+impl From<Value> for ToolOutput {
+    fn from(v: Value) -> Self {
+        Self::json(v)
+    }
+}
+
+pub(crate) fn to_proto_block(block: ContentBlock) -> pb::ToolContent {
+    match block {
+        ContentBlock::Text(text) => pb::ToolContent {
+            r#type: "text".into(),
+            text,
+            ..Default::default()
+        },
+        ContentBlock::Json(v) => pb::ToolContent {
+            r#type: "json".into(),
+            text: v.to_string(),
+            mime_type: "application/json".into(),
+            ..Default::default()
+        },
+        ContentBlock::Image { mime_type, data } => pb::ToolContent {
+            r#type: "image".into(),
+            data,
+            mime_type,
+            ..Default::default()
+        },
+        ContentBlock::File {
+            name,
+            mime_type,
+            uri,
+            data,
+        } => pb::ToolContent {
+            r#type: "file".into(),
+            data,
+            mime_type,
+            uri,
+            name,
+            ..Default::default()
+        },
+        ContentBlock::Citation { title, url } => pb::ToolContent {
+            r#type: "citation".into(),
+            text: title,
+            uri: url,
+            ..Default::default()
+        },
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::error::NeboError;
+use crate::tool::ToolHandler;
+
+/// A set of tools served by a single app, looked up by name.
+///
+/// The first registered tool is the default: it answers the legacy
+/// single-tool RPCs (`Name`, `Description`, `Schema`) and executes requests
+/// that do not name a tool.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn ToolHandler>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tool, builder-style.
+    pub fn register(mut self, h: impl ToolHandler) -> Self {
+        self.add(h);
+        self
+    }
+
+    /// Add a tool.
+    pub fn add(&mut self, h: impl ToolHandler) {
+        self.tools.push(Arc::new(h));
+    }
+
+    /// Move every tool from `other` into this registry.
+    pub fn extend(&mut self, other: ToolRegistry) {
+        self.tools.extend(other.tools);
+    }
+
+    /// Look up a tool by name.
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ToolHandler>> {
+        self.tools.iter().find(|t| t.name() == name)
+    }
+
+    /// Resolve a tool by name, falling back to the default tool for an empty name.
+    pub fn resolve(&self, name: &str) -> Option<&Arc<dyn ToolHandler>> {
+        if name.is_empty() {
+            self.default_tool()
+        } else {
+            self.get(name)
+        }
+    }
+
+    /// The first registered tool.
+    pub fn default_tool(&self) -> Option<&Arc<dyn ToolHandler>> {
+        self.tools.first()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn ToolHandler>> {
+        self.tools.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Ensure every tool name is unique.
+    pub fn check(&self) -> Result<(), NeboError> {
+        let mut seen = HashSet::new();
+        for t in &self.tools {
+            if !seen.insert(t.name()) {
+                return Err(NeboError::DuplicateTool(t.name().to_string()));
+            }
+        }
+        Ok(())
+    }
+}
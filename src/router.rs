@@ -0,0 +1,321 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::approval::{Approval, Preview};
+use crate::context::ToolContext;
+use crate::error::NeboError;
+use crate::output::ToolOutput;
+use crate::schema::{strap_schema, NeboSchema};
+use crate::tool::{parse_input, ToolHandler};
+
+type ActionFuture = Pin<Box<dyn Future<Output = Result<ToolOutput, NeboError>> + Send>>;
+type ActionFn = Box<dyn Fn(Value, ToolContext) -> ActionFuture + Send + Sync>;
+type ApprovalFn = Box<dyn Fn(&Value) -> Approval + Send + Sync>;
+type PreviewFuture = Pin<Box<dyn Future<Output = Result<Preview, NeboError>> + Send>>;
+type PreviewFn = Box<dyn Fn(Value, ToolContext) -> PreviewFuture + Send + Sync>;
+
+struct Action {
+    name: String,
+    params: Value,
+    handler: ActionFn,
+}
+
+/// A STRAP tool that dispatches on the `action` field of its input.
+///
+/// Each action registers its own parameter schema and async handler, which
+/// receives the call's [`ToolContext`] by value; the router builds the
+/// combined schema (the `action` enum plus every action's parameters) and
+/// rejects unknown actions before any handler runs. Actions may declare the
+/// same parameter differently; input is checked against the selected
+/// action's declaration.
+///
+/// # Panics
+///
+/// Registering an action name twice panics, as does calling
+/// [`approve_action`](Self::approve_action) or
+/// [`preview_action`](Self::preview_action) for an action that has not been
+/// registered yet.
+///
+/// ```no_run
+/// # use nebo_sdk::router::ActionRouter;
+/// # use nebo_sdk::{NeboSchema, SchemaBuilder};
+/// # #[derive(serde::Deserialize, NeboSchema)]
+/// # struct Operands { a: f64, b: f64 }
+/// let calc = ActionRouter::new("calculator", "Performs arithmetic calculations.")
+///     .typed_action("add", |o: Operands, _ctx| async move { Ok(format!("{}", o.a + o.b)) })
+///     .action(
+///         "negate",
+///         SchemaBuilder::new(&[]).number("a", "Operand", true).build(),
+///         |input, _ctx| async move { Ok(format!("{}", -input["a"].as_f64().unwrap_or(0.0))) },
+///     );
+/// ```
+pub struct ActionRouter {
+    name: String,
+    description: String,
+    actions: Vec<Action>,
+    requires_approval: bool,
+    approvals: HashMap<String, ApprovalFn>,
+    previews: HashMap<String, PreviewFn>,
+    reports_progress: bool,
+    timeout: Option<Duration>,
+}
+
+impl ActionRouter {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            actions: Vec::new(),
+            requires_approval: false,
+            approvals: HashMap::new(),
+            previews: HashMap::new(),
+            reports_progress: false,
+            timeout: None,
+        }
+    }
+
+    /// Register an action handled by an async closure over the raw input. The
+    /// closure may return anything convertible into a [`ToolOutput`], such as
+    /// a `String`. `params` is an object schema, e.g. from `SchemaBuilder::new(&[])`.
+    pub fn action<F, Fut, O>(mut self, name: &str, params: Value, f: F) -> Self
+    where
+        F: Fn(Value, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, NeboError>> + Send + 'static,
+        O: Into<ToolOutput>,
+    {
+        self.assert_new_action(name);
+        self.actions.push(Action {
+            name: name.to_string(),
+            params,
+            handler: Box::new(move |input, ctx| {
+                let fut = f(input, ctx);
+                Box::pin(async move { fut.await.map(Into::into) })
+            }),
+        });
+        self
+    }
+
+    /// Register an action whose parameters are deserialized into `I`. The
+    /// parameter schema comes from `I::nebo_schema()`.
+    pub fn typed_action<I, F, Fut, O>(mut self, name: &str, f: F) -> Self
+    where
+        I: DeserializeOwned + NeboSchema + Send + 'static,
+        F: Fn(I, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, NeboError>> + Send + 'static,
+        O: Into<ToolOutput>,
+    {
+        self.assert_new_action(name);
+        let tool = self.name.clone();
+        let f = std::sync::Arc::new(f);
+        self.actions.push(Action {
+            name: name.to_string(),
+            params: I::nebo_schema(),
+            handler: Box::new(move |input, ctx| {
+                let parsed = parse_input::<I>(&tool, input);
+                let f = f.clone();
+                Box::pin(async move { f(parsed?, ctx).await.map(Into::into) })
+            }),
+        });
+        self
+    }
+
+    /// Require user approval before any action runs.
+    pub fn requires_approval(mut self, yes: bool) -> Self {
+        self.requires_approval = yes;
+        self
+    }
+
+    /// Decide approval for calls to one action from its input, e.g. to ask
+    /// before `delete` while letting `read` run freely. Overrides
+    /// [`requires_approval`](Self::requires_approval) for that action.
+    pub fn approve_action<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(&Value) -> Approval + Send + Sync + 'static,
+    {
+        self.assert_action(name, "approve_action");
+        self.approvals.insert(name.to_string(), Box::new(f));
+        self
+    }
+
+    /// Compute a side-effect-free preview for calls to one action.
+    pub fn preview_action<F, Fut>(mut self, name: &str, f: F) -> Self
+    where
+        F: Fn(Value, ToolContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Preview, NeboError>> + Send + 'static,
+    {
+        self.assert_action(name, "preview_action");
+        self.previews
+            .insert(name.to_string(), Box::new(move |input, ctx| Box::pin(f(input, ctx))));
+        self
+    }
+
+    /// Declare that actions report through [`ToolContext::progress`].
+    pub fn reports_progress(mut self, yes: bool) -> Self {
+        self.reports_progress = yes;
+        self
+    }
+
+    /// Maximum run time for one call to any action.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn action_names(&self) -> Vec<&str> {
+        self.actions.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    /// A second handler for `name` could never run.
+    fn assert_new_action(&self, name: &str) {
+        if self.actions.iter().any(|a| a.name == name) {
+            panic!("tool `{}` registers action `{}` more than once", self.name, name);
+        }
+    }
+
+    /// Settings for an unknown action would never apply.
+    fn assert_action(&self, name: &str, method: &str) {
+        if !self.actions.iter().any(|a| a.name == name) {
+            panic!(
+                "tool `{}` calls {} for unregistered action `{}`; register the action first",
+                self.name, method, name
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl ToolHandler for ActionRouter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn schema(&self) -> Value {
+        let actions = self
+            .actions
+            .iter()
+            .map(|a| (a.name.clone(), a.params.clone()))
+            .collect();
+        strap_schema("action", actions)
+    }
+
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let requested = input.get("action").and_then(Value::as_str).unwrap_or_default();
+        let Some(action) = self.actions.iter().find(|a| a.name == requested) else {
+            let message = if requested.is_empty() {
+                format!("missing `action`; expected one of: {}", self.action_names().join(", "))
+            } else {
+                format!(
+                    "unknown action `{}`; expected one of: {}",
+                    requested,
+                    self.action_names().join(", ")
+                )
+            };
+            return Err(NeboError::InvalidInput {
+                tool: self.name.clone(),
+                message,
+            });
+        };
+        (action.handler)(input, ctx.clone()).await
+    }
+
+    fn requires_approval(&self) -> bool {
+        self.requires_approval || !self.approvals.is_empty()
+    }
+
+    async fn approval(&self, input: &Value, _ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let action = input.get("action").and_then(Value::as_str).unwrap_or_default();
+        Ok(match self.approvals.get(action) {
+            Some(f) => f(input),
+            None => self.requires_approval.into(),
+        })
+    }
+
+    async fn preview(&self, input: &Value, ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let action = input.get("action").and_then(Value::as_str).unwrap_or_default();
+        match self.previews.get(action) {
+            Some(f) => f(input.clone(), ctx.clone()).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn reports_progress(&self) -> bool {
+        self.reports_progress
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaBuilder;
+    use crate::validate::validate;
+    use serde_json::json;
+
+    fn add() -> ActionRouter {
+        ActionRouter::new("calc", "Calculator.")
+            .action("add", SchemaBuilder::new(&[]).build(), |_, _| async { Ok("one") })
+    }
+
+    #[test]
+    #[should_panic(expected = "tool `calc` registers action `add` more than once")]
+    fn repeated_action_is_rejected() {
+        add().action("add", SchemaBuilder::new(&[]).build(), |_, _| async { Ok("two") });
+    }
+
+    #[test]
+    #[should_panic(expected = "tool `calc` registers action `add` more than once")]
+    fn repeated_typed_action_is_rejected() {
+        #[derive(serde::Deserialize)]
+        struct Empty {}
+        impl NeboSchema for Empty {
+            fn nebo_schema() -> Value {
+                SchemaBuilder::new(&[]).build()
+            }
+        }
+        add().typed_action("add", |_: Empty, _| async { Ok("two") });
+    }
+
+    #[test]
+    #[should_panic(expected = "calls approve_action for unregistered action `delete`")]
+    fn approval_for_unknown_action_is_rejected() {
+        add().approve_action("delete", |_| Approval::not_required());
+    }
+
+    #[test]
+    #[should_panic(expected = "calls preview_action for unregistered action `delete`")]
+    fn preview_for_unknown_action_is_rejected() {
+        add().preview_action("delete", |_, _| async { Ok(Preview::default()) });
+    }
+
+    #[tokio::test]
+    async fn actions_with_conflicting_params_each_get_their_schema() {
+        let router = ActionRouter::new("calc", "Calculator.")
+            .action("one", SchemaBuilder::new(&[]).string("x", "", true).build(), |_, _| async {
+                Ok("one")
+            })
+            .action("two", SchemaBuilder::new(&[]).number("x", "", true).build(), |_, _| async {
+                Ok("two")
+            });
+        let schema = router.schema();
+        assert!(validate(&schema, &json!({"action": "two", "x": 3})).is_empty());
+        assert!(!validate(&schema, &json!({"action": "one", "x": 3})).is_empty());
+
+        let out = router
+            .execute(json!({"action": "two", "x": 3}), &ToolContext::default())
+            .await
+            .unwrap();
+        assert_eq!(out.to_text(), "two");
+    }
+}
@@ -1,10 +1,13 @@
 use async_trait::async_trait;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status};
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
 use crate::pb;
+use crate::settings::Settings;
+use crate::shutdown::ShutdownSignal;
 
 /// Re-export protobuf schedule types for handler implementations.
 pub use pb::Schedule;
@@ -26,11 +29,20 @@ pub trait ScheduleHandler: Send + Sync + 'static {
     async fn trigger(&self, name: &str) -> Result<(bool, String), NeboError>;
     async fn history(&self, name: &str, limit: i32, offset: i32) -> Result<(Vec<ScheduleHistoryEntry>, i64), NeboError>;
     async fn triggers(&self) -> Result<mpsc::Receiver<ScheduleTrigger>, NeboError>;
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once on shutdown after the `Triggers` stream is closed.
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
 }
 
 pub(crate) struct ScheduleBridge {
-    pub handler: Box<dyn ScheduleHandler>,
-    pub on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    pub handler: Arc<dyn ScheduleHandler>,
+    pub settings: Settings,
+    pub shutdown: ShutdownSignal,
     pub env: AppEnv,
 }
 
@@ -110,8 +122,16 @@ impl pb::schedule_service_server::ScheduleService for ScheduleBridge {
     async fn triggers(&self, _req: Request<pb::Empty>) -> Result<Response<Self::TriggersStream>, Status> {
         let mut rx = self.handler.triggers().await.map_err(|e| Status::internal(e.to_string()))?;
         let (tx, stream_rx) = mpsc::channel(100);
+        let mut shutdown = self.shutdown.clone();
         tokio::spawn(async move {
-            while let Some(trigger) = rx.recv().await {
+            loop {
+                let trigger = tokio::select! {
+                    trigger = rx.recv() => match trigger {
+                        Some(trigger) => trigger,
+                        None => break,
+                    },
+                    _ = shutdown.wait() => break,
+                };
                 if tx.send(Ok(trigger)).await.is_err() {
                     break;
                 }
@@ -121,9 +141,9 @@ impl pb::schedule_service_server::ScheduleService for ScheduleBridge {
     }
 
     async fn configure(&self, req: Request<pb::SettingsMap>) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
 }
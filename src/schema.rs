@@ -1,10 +1,360 @@
 use serde_json::{json, Value};
 
+/// Types that describe their own JSON Schema, used as typed tool input.
+///
+/// Derive it with `#[derive(NeboSchema)]`: doc comments become descriptions,
+/// `Option<T>` and `#[serde(default)]` fields are optional, and an enum with
+/// `#[serde(tag = "action")]` produces the same STRAP schema as
+/// [`SchemaBuilder::build`].
+pub trait NeboSchema {
+    fn nebo_schema() -> Value;
+}
+
+pub use nebo_sdk_macros::NeboSchema;
+
+pub mod compat;
+pub mod export;
+pub mod lint;
+
+macro_rules! impl_schema {
+    ($kind:literal: $($ty:ty),*) => {
+        $(
+            impl NeboSchema for $ty {
+                fn nebo_schema() -> Value {
+                    json!({"type": $kind})
+                }
+            }
+        )*
+    };
+}
+
+impl_schema!("string": String, str, char);
+impl_schema!("boolean": bool);
+impl_schema!("integer": i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_schema!("number": f32, f64);
+
+impl NeboSchema for Value {
+    fn nebo_schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: NeboSchema + ?Sized> NeboSchema for Box<T> {
+    fn nebo_schema() -> Value {
+        T::nebo_schema()
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for Option<T> {
+    fn nebo_schema() -> Value {
+        T::nebo_schema()
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for Vec<T> {
+    fn nebo_schema() -> Value {
+        json!({"type": "array", "items": T::nebo_schema()})
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for std::collections::HashSet<T> {
+    fn nebo_schema() -> Value {
+        json!({"type": "array", "items": T::nebo_schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for std::collections::BTreeSet<T> {
+    fn nebo_schema() -> Value {
+        json!({"type": "array", "items": T::nebo_schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for std::collections::HashMap<String, T> {
+    fn nebo_schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::nebo_schema()})
+    }
+}
+
+impl<T: NeboSchema> NeboSchema for std::collections::BTreeMap<String, T> {
+    fn nebo_schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::nebo_schema()})
+    }
+}
+
+/// Support code for `#[derive(NeboSchema)]`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    use serde_json::{json, Map, Value};
+
+    pub use serde_json::Value as JsonValue;
+
+    pub struct Field {
+        pub name: &'static str,
+        pub schema: Value,
+        pub doc: &'static str,
+        pub required: bool,
+        pub flatten: bool,
+    }
+
+    fn describe(mut schema: Value, doc: &str) -> Value {
+        if let (false, Some(obj)) = (doc.is_empty(), schema.as_object_mut()) {
+            obj.insert("description".to_string(), json!(doc));
+        }
+        schema
+    }
+
+    fn merge(props: &mut Map<String, Value>, required: &mut Vec<String>, fields: Vec<Field>) {
+        for f in fields {
+            if f.flatten {
+                if let Some(inner) = f.schema.get("properties").and_then(Value::as_object) {
+                    for (k, v) in inner {
+                        props.entry(k.clone()).or_insert_with(|| v.clone());
+                    }
+                }
+                if f.required {
+                    if let Some(req) = f.schema.get("required").and_then(Value::as_array) {
+                        required.extend(req.iter().filter_map(Value::as_str).map(String::from));
+                    }
+                }
+                continue;
+            }
+            props
+                .entry(f.name.to_string())
+                .or_insert_with(|| describe(f.schema, f.doc));
+            if f.required {
+                required.push(f.name.to_string());
+            }
+        }
+    }
+
+    /// Schema for a struct with named fields.
+    pub fn object(doc: &str, fields: Vec<Field>) -> Value {
+        let mut props = Map::new();
+        let mut required = Vec::new();
+        merge(&mut props, &mut required, fields);
+        describe(
+            json!({"type": "object", "properties": props, "required": required}),
+            doc,
+        )
+    }
+
+    /// Schema for an enum of unit variants.
+    pub fn string_enum(doc: &str, values: &[&str]) -> Value {
+        describe(json!({"type": "string", "enum": values}), doc)
+    }
+
+    /// Schema for a field whose type is only known to be a newtype around
+    /// another schema; used for newtype variants of tagged enums.
+    pub fn newtype(schema: Value) -> Vec<Field> {
+        vec![Field {
+            name: "",
+            schema,
+            doc: "",
+            required: true,
+            flatten: true,
+        }]
+    }
+
+    /// STRAP schema for an internally tagged enum: a `tag` property listing
+    /// every variant plus the union of all variants' parameters. Parameters
+    /// required by every variant are required overall.
+    pub fn tagged(doc: &str, tag: &str, variants: Vec<(&'static str, Vec<Field>)>) -> Value {
+        let actions = variants
+            .into_iter()
+            .map(|(name, fields)| (name.to_string(), object("", fields)))
+            .collect();
+        describe(super::strap_schema(tag, actions), doc)
+    }
+
+    /// Add the STRAP "Action to perform" description to an `action` field
+    /// whose schema is an enum without its own description.
+    pub fn action_field(mut schema: Value) -> Value {
+        let values: Option<Vec<String>> = schema.get("enum").and_then(Value::as_array).map(|v| {
+            v.iter().filter_map(Value::as_str).map(String::from).collect()
+        });
+        if let (Some(values), Some(obj)) = (values, schema.as_object_mut()) {
+            obj.entry("description")
+                .or_insert_with(|| json!(format!("Action to perform: {}", values.join(", "))));
+        }
+        schema
+    }
+}
+
+/// Merge per-action parameter schemas into one STRAP schema: a `tag` enum
+/// listing every action plus the union of all actions' properties.
+/// Properties required by every action are required overall; the rest are
+/// required per action through `allOf` `if`/`then` clauses. A property that
+/// actions declare with different schemas is checked against the selected
+/// action's schema in the same clauses, and accepts any of their types
+/// overall.
+pub(crate) fn strap_schema(tag: &str, actions: Vec<(String, Value)>) -> Value {
+    let names: Vec<&str> = actions.iter().map(|(name, _)| name.as_str()).collect();
+    let mut props = serde_json::Map::new();
+    props.insert(
+        tag.to_string(),
+        json!({
+            "type": "string",
+            "enum": names,
+            "description": format!("Action to perform: {}", names.join(", ")),
+        }),
+    );
+
+    // property -> every (action, schema) declaring it, in declaration order
+    let mut declared: Vec<(String, Vec<(String, Value)>)> = Vec::new();
+    let mut common: Option<Vec<Value>> = None;
+    let mut per_action = Vec::new();
+    for (name, params) in &actions {
+        if let Some(p) = params.get("properties").and_then(Value::as_object) {
+            for (k, v) in p {
+                let def = (name.clone(), v.clone());
+                match declared.iter_mut().find(|(prop, _)| prop == k) {
+                    Some((_, defs)) => defs.push(def),
+                    None => declared.push((k.clone(), vec![def])),
+                }
+            }
+        }
+        let required = params
+            .get("required")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        common = Some(match common {
+            None => required.clone(),
+            Some(prev) => prev.into_iter().filter(|r| required.contains(r)).collect(),
+        });
+        per_action.push((name.clone(), serde_json::Map::new(), required));
+    }
+
+    for (prop, defs) in declared {
+        let first = without_description(&defs[0].1);
+        if defs.iter().all(|(_, d)| without_description(d) == first) {
+            props.insert(prop, defs[0].1.clone());
+            continue;
+        }
+        props.insert(prop.clone(), any_of_types(&defs));
+        for (action, def) in defs {
+            if let Some((_, scoped, _)) = per_action.iter_mut().find(|(name, _, _)| *name == action) {
+                scoped.insert(prop.clone(), def);
+            }
+        }
+    }
+
+    let common = common.unwrap_or_default();
+    let per_action = per_action
+        .into_iter()
+        .map(|(name, scoped, required)| {
+            let extra = required.into_iter().filter(|r| !common.contains(r)).collect();
+            (name, scoped, extra)
+        })
+        .collect();
+
+    let mut required = vec![json!(tag)];
+    required.extend(common);
+    let mut schema = json!({
+        "type": "object",
+        "properties": props,
+        "required": required,
+    });
+    add_action_requirements(&mut schema, tag, per_action);
+    schema
+}
+
+fn without_description(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    if let Some(obj) = schema.as_object_mut() {
+        obj.remove("description");
+    }
+    schema
+}
+
+/// Overall schema of a property declared differently by several actions:
+/// the union of their types, if they all have one, and the first description.
+fn any_of_types(defs: &[(String, Value)]) -> Value {
+    let mut types: Vec<Value> = Vec::new();
+    let mut typed = true;
+    for (_, def) in defs {
+        let ts = match def.get("type") {
+            Some(Value::String(t)) => vec![json!(t)],
+            Some(Value::Array(ts)) => ts.clone(),
+            _ => {
+                typed = false;
+                break;
+            }
+        };
+        for t in ts {
+            if !types.contains(&t) {
+                types.push(t);
+            }
+        }
+    }
+    let mut out = serde_json::Map::new();
+    if typed {
+        let t = if types.len() == 1 { types.remove(0) } else { Value::Array(types) };
+        out.insert("type".to_string(), t);
+    }
+    if let Some(d) = defs.iter().find_map(|(_, d)| d.get("description")) {
+        out.insert("description".to_string(), d.clone());
+    }
+    Value::Object(out)
+}
+
+/// Add an `allOf` clause that, when `tag` selects an action, requires its
+/// extra fields and checks its own schemas for the given properties.
+/// Actions with neither are skipped.
+fn add_action_requirements(
+    schema: &mut Value,
+    tag: &str,
+    per_action: Vec<(String, serde_json::Map<String, Value>, Vec<Value>)>,
+) {
+    let clauses: Vec<Value> = per_action
+        .into_iter()
+        .filter(|(_, scoped, required)| !scoped.is_empty() || !required.is_empty())
+        .map(|(action, scoped, required)| {
+            let mut then = json!({"required": required});
+            if !scoped.is_empty() {
+                then["properties"] = Value::Object(scoped);
+            }
+            json!({
+                "if": {"properties": {tag: {"const": action}}, "required": [tag]},
+                "then": then,
+            })
+        })
+        .collect();
+    if let (false, Some(obj)) = (clauses.is_empty(), schema.as_object_mut()) {
+        obj.insert("allOf".to_string(), Value::Array(clauses));
+    }
+}
+
+/// Describe the `const` properties an `if` clause tests, e.g.
+/// `` `action` is "divide" `` for the clauses of [`add_action_requirements`].
+/// Returns `None` for any other condition.
+pub(crate) fn describe_condition(cond: &Value) -> Option<String> {
+    let props = cond.get("properties")?.as_object()?;
+    let parts: Vec<String> = props
+        .iter()
+        .filter_map(|(k, v)| v.get("const").map(|c| format!("`{k}` is {c}")))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" and "))
+}
+
+/// Location of property `key` inside `path`, e.g. `filter.year`.
+pub(crate) fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
 /// Builder for JSON Schema following the STRAP pattern.
+///
+/// An empty action list builds a plain object schema without the `action`
+/// property, e.g. for per-action parameters in an [`ActionRouter`](crate::router::ActionRouter).
 pub struct SchemaBuilder {
     actions: Vec<String>,
     properties: Vec<(String, Value)>,
     required: Vec<String>,
+    action_required: Vec<(String, Vec<String>)>,
 }
 
 impl SchemaBuilder {
@@ -14,83 +364,303 @@ impl SchemaBuilder {
             actions: actions.iter().map(|s| s.to_string()).collect(),
             properties: Vec::new(),
             required: Vec::new(),
+            action_required: Vec::new(),
         }
     }
 
     /// Add a string parameter.
-    pub fn string(mut self, name: &str, description: &str, required: bool) -> Self {
-        self.properties.push((
-            name.to_string(),
-            json!({"type": "string", "description": description}),
-        ));
-        if required {
-            self.required.push(name.to_string());
-        }
-        self
+    pub fn string(self, name: &str, description: &str, required: bool) -> Self {
+        self.property(name, Prop::string().description(description), required)
     }
 
     /// Add a number parameter.
-    pub fn number(mut self, name: &str, description: &str, required: bool) -> Self {
-        self.properties.push((
-            name.to_string(),
-            json!({"type": "number", "description": description}),
-        ));
-        if required {
-            self.required.push(name.to_string());
-        }
-        self
+    pub fn number(self, name: &str, description: &str, required: bool) -> Self {
+        self.property(name, Prop::number().description(description), required)
+    }
+
+    /// Add an integer parameter.
+    pub fn integer(self, name: &str, description: &str, required: bool) -> Self {
+        self.property(name, Prop::integer().description(description), required)
     }
 
     /// Add a boolean parameter.
-    pub fn boolean(mut self, name: &str, description: &str, required: bool) -> Self {
-        self.properties.push((
-            name.to_string(),
-            json!({"type": "boolean", "description": description}),
-        ));
+    pub fn boolean(self, name: &str, description: &str, required: bool) -> Self {
+        self.property(name, Prop::boolean().description(description), required)
+    }
+
+    /// Add a string enum parameter.
+    pub fn enum_field(self, name: &str, description: &str, required: bool, values: &[&str]) -> Self {
+        self.property(name, Prop::enum_of(values).description(description), required)
+    }
+
+    /// Add an array parameter whose elements match `items`.
+    pub fn array(self, name: &str, description: &str, required: bool, items: Prop) -> Self {
+        self.property(name, Prop::array(items).description(description), required)
+    }
+
+    /// Add a nested object parameter described by another builder, which
+    /// should have no actions.
+    pub fn object(self, name: &str, description: &str, required: bool, fields: SchemaBuilder) -> Self {
+        self.property(name, Prop::object(fields).description(description), required)
+    }
+
+    /// Add a parameter with a fully specified schema.
+    pub fn property(mut self, name: &str, prop: Prop, required: bool) -> Self {
+        self.properties.push((name.to_string(), prop.into()));
         if required {
             self.required.push(name.to_string());
         }
         self
     }
 
-    /// Add a string enum parameter.
-    pub fn enum_field(mut self, name: &str, description: &str, required: bool, values: &[&str]) -> Self {
-        self.properties.push((
-            name.to_string(),
-            json!({"type": "string", "enum": values, "description": description}),
+    /// Require `fields` only when `action` is selected, on top of the
+    /// parameters marked required for every action. Add the fields
+    /// themselves as optional parameters.
+    pub fn action_requires(mut self, action: &str, fields: &[&str]) -> Self {
+        self.action_required.push((
+            action.to_string(),
+            fields.iter().map(|f| f.to_string()).collect(),
         ));
-        if required {
-            self.required.push(name.to_string());
-        }
         self
     }
 
     /// Build the JSON Schema as a serde_json::Value.
     pub fn build(self) -> Value {
-        let action_desc = self.actions.join(", ");
         let mut props = serde_json::Map::new();
-        props.insert(
-            "action".to_string(),
-            json!({
-                "type": "string",
-                "enum": self.actions,
-                "description": format!("Action to perform: {}", action_desc),
-            }),
-        );
+        let mut req: Vec<Value> = Vec::new();
+        if !self.actions.is_empty() {
+            let action_desc = self.actions.join(", ");
+            props.insert(
+                "action".to_string(),
+                json!({
+                    "type": "string",
+                    "enum": self.actions,
+                    "description": format!("Action to perform: {}", action_desc),
+                }),
+            );
+            req.push(json!("action"));
+        }
 
         for (name, schema) in self.properties {
             props.insert(name, schema);
         }
 
-        let mut req: Vec<Value> = vec![json!("action")];
         for r in self.required {
             req.push(json!(r));
         }
 
-        json!({
+        let mut schema = json!({
             "type": "object",
             "properties": props,
             "required": req,
-        })
+        });
+        let per_action = self
+            .action_required
+            .into_iter()
+            .map(|(action, fields)| {
+                let fields = fields.into_iter().map(Value::from).collect();
+                (action, serde_json::Map::new(), fields)
+            })
+            .collect();
+        add_action_requirements(&mut schema, "action", per_action);
+        schema
+    }
+}
+
+/// Schema of a single property, for [`SchemaBuilder::property`] and as the
+/// element type of arrays.
+///
+/// ```
+/// # use nebo_sdk::schema::{Prop, SchemaBuilder};
+/// let schema = SchemaBuilder::new(&["search"])
+///     .property("query", Prop::string().description("Search terms").min_length(1), true)
+///     .property("limit", Prop::integer().minimum(1).maximum(100).default(10), false)
+///     .array("tags", "Tags to filter by", false, Prop::string().pattern("^[a-z]+$"))
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prop {
+    schema: serde_json::Map<String, Value>,
+}
+
+impl Prop {
+    fn of_type(kind: &str) -> Self {
+        let mut schema = serde_json::Map::new();
+        schema.insert("type".to_string(), json!(kind));
+        Self { schema }
+    }
+
+    pub fn string() -> Self {
+        Self::of_type("string")
+    }
+
+    pub fn number() -> Self {
+        Self::of_type("number")
+    }
+
+    pub fn integer() -> Self {
+        Self::of_type("integer")
+    }
+
+    pub fn boolean() -> Self {
+        Self::of_type("boolean")
+    }
+
+    /// A string restricted to `values`.
+    pub fn enum_of(values: &[&str]) -> Self {
+        Self::string().set("enum", json!(values))
+    }
+
+    /// An array whose elements match `items`.
+    pub fn array(items: Prop) -> Self {
+        Self::of_type("array").set("items", items.into())
+    }
+
+    /// An object with the fields of `fields`, which should have no actions.
+    pub fn object(fields: SchemaBuilder) -> Self {
+        match fields.build() {
+            Value::Object(schema) => Self { schema },
+            _ => unreachable!("SchemaBuilder always builds an object"),
+        }
+    }
+
+    /// Wrap an existing JSON Schema; anything but an object accepts any value.
+    pub fn from_schema(schema: Value) -> Self {
+        match schema {
+            Value::Object(schema) => Self { schema },
+            _ => Self {
+                schema: serde_json::Map::new(),
+            },
+        }
+    }
+
+    pub fn description(self, description: &str) -> Self {
+        self.set("description", json!(description))
+    }
+
+    pub fn default(self, value: impl Into<Value>) -> Self {
+        self.set("default", value.into())
+    }
+
+    /// Add an example value. May be called repeatedly.
+    pub fn example(mut self, value: impl Into<Value>) -> Self {
+        let examples = self
+            .schema
+            .entry("examples")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(list) = examples {
+            list.push(value.into());
+        }
+        self
+    }
+
+    pub fn minimum(self, min: impl Into<Value>) -> Self {
+        self.set("minimum", min.into())
+    }
+
+    pub fn maximum(self, max: impl Into<Value>) -> Self {
+        self.set("maximum", max.into())
+    }
+
+    pub fn min_length(self, len: u64) -> Self {
+        self.set("minLength", json!(len))
+    }
+
+    pub fn max_length(self, len: u64) -> Self {
+        self.set("maxLength", json!(len))
+    }
+
+    /// A regular expression strings must match.
+    pub fn pattern(self, pattern: &str) -> Self {
+        self.set("pattern", json!(pattern))
+    }
+
+    /// A semantic format hint such as `date-time`, `email` or `uri`.
+    pub fn format(self, format: &str) -> Self {
+        self.set("format", json!(format))
+    }
+
+    pub fn min_items(self, n: u64) -> Self {
+        self.set("minItems", json!(n))
+    }
+
+    pub fn max_items(self, n: u64) -> Self {
+        self.set("maxItems", json!(n))
+    }
+
+    pub fn unique_items(self) -> Self {
+        self.set("uniqueItems", json!(true))
+    }
+
+    /// Set any other JSON Schema keyword.
+    pub fn set(mut self, keyword: &str, value: Value) -> Self {
+        self.schema.insert(keyword.to_string(), value);
+        self
+    }
+}
+
+impl From<Prop> for Value {
+    fn from(prop: Prop) -> Self {
+        Value::Object(prop.schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+
+    fn params(prop: Prop) -> Value {
+        SchemaBuilder::new(&[]).property("x", prop, true).build()
+    }
+
+    #[test]
+    fn conflicting_params_are_checked_per_action() {
+        let schema = strap_schema(
+            "action",
+            vec![
+                ("one".to_string(), params(Prop::string())),
+                ("two".to_string(), params(Prop::number())),
+            ],
+        );
+        assert_eq!(schema["properties"]["x"]["type"], json!(["string", "number"]));
+        assert!(validate(&schema, &json!({"action": "one", "x": "a"})).is_empty());
+        assert!(validate(&schema, &json!({"action": "two", "x": 3})).is_empty());
+
+        let errors = validate(&schema, &json!({"action": "two", "x": "a"}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "x");
+        assert_eq!(errors[0].message, "must be number, got string when `action` is \"two\"");
+    }
+
+    #[test]
+    fn params_differing_only_in_description_are_shared() {
+        let schema = strap_schema(
+            "action",
+            vec![
+                ("one".to_string(), params(Prop::string().description("First"))),
+                ("two".to_string(), params(Prop::string().description("Second"))),
+            ],
+        );
+        assert_eq!(schema["properties"]["x"], json!({"type": "string", "description": "First"}));
+        assert!(schema.get("allOf").is_none());
+        assert_eq!(schema["required"], json!(["action", "x"]));
+    }
+
+    #[test]
+    fn action_requires_adds_conditional_clause() {
+        let schema = SchemaBuilder::new(&["add", "divide"])
+            .number("a", "", true)
+            .number("b", "", false)
+            .action_requires("divide", &["b"])
+            .build();
+        assert_eq!(schema["required"], json!(["action", "a"]));
+        assert_eq!(
+            schema["allOf"],
+            json!([{
+                "if": {"properties": {"action": {"const": "divide"}}, "required": ["action"]},
+                "then": {"required": ["b"]},
+            }])
+        );
     }
 }
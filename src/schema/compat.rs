@@ -0,0 +1,377 @@
+//! Compare two versions of a tool input schema.
+//!
+//! [`diff`] lists what changed between an old and a new schema and marks the
+//! changes that break callers still sending input for the old one: removed
+//! properties or actions, newly required fields (overall or per action),
+//! narrowed types and tightened bounds.
+//!
+//! ```
+//! # use nebo_sdk::SchemaBuilder;
+//! # use nebo_sdk::schema::compat::{diff, is_breaking};
+//! let old = SchemaBuilder::new(&["add", "divide"]).number("a", "", true).build();
+//! let new = SchemaBuilder::new(&["add"]).number("a", "", true).build();
+//! assert!(is_breaking(&diff(&old, &new)));
+//! ```
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::schema::{describe_condition, join_path};
+
+/// One difference between two schema versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Whether input valid for the old schema may be rejected by the new one.
+    pub breaking: bool,
+    /// Location of the change, e.g. `filter.year` or `tags[]`; empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.breaking { "breaking" } else { "compatible" };
+        if self.path.is_empty() {
+            write!(f, "{}: {}", kind, self.message)
+        } else {
+            write!(f, "{}: `{}` {}", kind, self.path, self.message)
+        }
+    }
+}
+
+/// List the changes from `old` to `new`.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut out = Vec::new();
+    compare(old, new, "", &mut out);
+    out
+}
+
+/// Whether any change breaks existing callers.
+pub fn is_breaking(changes: &[Change]) -> bool {
+    changes.iter().any(|c| c.breaking)
+}
+
+fn compare(old: &Value, new: &Value, path: &str, out: &mut Vec<Change>) {
+    let (old_types, new_types) = (types(old), types(new));
+    if old_types != new_types {
+        // Every integer is a number, so integer -> number is a widening.
+        let kept = |t: &String| {
+            new_types.contains(t) || (t == "integer" && new_types.iter().any(|n| n == "number"))
+        };
+        let narrowed = !new_types.is_empty() && (old_types.is_empty() || !old_types.iter().all(kept));
+        push(
+            out,
+            narrowed,
+            path,
+            format!("type changed from {} to {}", type_list(&old_types), type_list(&new_types)),
+        );
+    }
+
+    let old_enum = values(old.get("enum"));
+    let new_enum = values(new.get("enum"));
+    if let (Some(old_enum), Some(new_enum)) = (&old_enum, &new_enum) {
+        let noun = if path == "action" { "action" } else { "value" };
+        for v in old_enum.iter().filter(|v| !new_enum.contains(v)) {
+            push(out, true, path, format!("{noun} {v} was removed"));
+        }
+        for v in new_enum.iter().filter(|v| !old_enum.contains(v)) {
+            push(out, false, path, format!("{noun} {v} was added"));
+        }
+    } else if old_enum.is_none() && new_enum.is_some() {
+        push(out, true, path, "is now restricted to an enum".to_string());
+    }
+
+    for (key, tighter) in [
+        ("minimum", Ordering::Greater),
+        ("exclusiveMinimum", Ordering::Greater),
+        ("minLength", Ordering::Greater),
+        ("minItems", Ordering::Greater),
+        ("maximum", Ordering::Less),
+        ("exclusiveMaximum", Ordering::Less),
+        ("maxLength", Ordering::Less),
+        ("maxItems", Ordering::Less),
+    ] {
+        let a = old.get(key).and_then(Value::as_f64);
+        let b = new.get(key).and_then(Value::as_f64);
+        match (a, b) {
+            (None, Some(b)) => push(out, true, path, format!("added `{key}` {b}")),
+            (Some(a), Some(b)) if b.partial_cmp(&a) == Some(tighter) => {
+                push(out, true, path, format!("`{key}` tightened from {a} to {b}"))
+            }
+            (Some(a), Some(b)) if a != b => {
+                push(out, false, path, format!("`{key}` relaxed from {a} to {b}"))
+            }
+            (Some(a), None) => push(out, false, path, format!("removed `{key}` {a}")),
+            _ => {}
+        }
+    }
+    if old.get("pattern") != new.get("pattern") {
+        if let Some(p) = new.get("pattern") {
+            push(out, true, path, format!("pattern changed to {p}"));
+        }
+    }
+
+    let old_props = props(old);
+    let new_props = props(new);
+    let old_req = required(old);
+    let new_req = required(new);
+    for (name, old_sub) in &old_props {
+        let at = join_path(path, name);
+        match new_props.get(name) {
+            None => push(out, true, &at, "was removed".to_string()),
+            Some(new_sub) => compare(old_sub, new_sub, &at, out),
+        }
+    }
+    let old_cond = conditional_required(old);
+    let new_cond = conditional_required(new);
+    for name in new_props.keys().filter(|n| !old_props.contains_key(*n)) {
+        let at = join_path(path, name);
+        if new_req.contains(name) {
+            push(out, true, &at, "was added as a required property".to_string());
+        } else if !new_cond.values().any(|fields| fields.contains(name)) {
+            // Properties required under a condition are reported below.
+            push(out, false, &at, "was added".to_string());
+        }
+    }
+    for name in new_req.iter().filter(|n| !old_req.contains(n) && old_props.contains_key(*n)) {
+        push(out, true, &join_path(path, name), "is now required".to_string());
+    }
+    for name in old_req.iter().filter(|n| !new_req.contains(n) && new_props.contains_key(*n)) {
+        push(out, false, &join_path(path, name), "is no longer required".to_string());
+    }
+
+    for (when, fields) in &new_cond {
+        let before = old_cond.get(when).cloned().unwrap_or_default();
+        for f in fields.iter().filter(|f| !before.contains(f) && !new_req.contains(f)) {
+            let message = if old_props.contains_key(f) {
+                format!("is now required when {when}")
+            } else {
+                format!("was added as a required property when {when}")
+            };
+            push(out, true, &join_path(path, f), message);
+        }
+    }
+    for (when, fields) in &old_cond {
+        let after = new_cond.get(when).cloned().unwrap_or_default();
+        for f in fields.iter().filter(|f| !after.contains(f) && new_props.contains_key(*f)) {
+            push(out, false, &join_path(path, f), format!("is no longer required when {when}"));
+        }
+    }
+
+    // Properties that actions declare differently are checked per action;
+    // compare what each action accepted before and after.
+    let old_scoped = conditional_properties(old);
+    let new_scoped = conditional_properties(new);
+    let conditions: BTreeSet<&String> = old_scoped.keys().chain(new_scoped.keys()).collect();
+    for when in conditions {
+        let (before, after) = (old_scoped.get(when), new_scoped.get(when));
+        let names: BTreeSet<&String> = before.into_iter().chain(after).flat_map(|p| p.keys()).collect();
+        for name in names {
+            let a = before.and_then(|p| p.get(name)).or(old_props.get(name));
+            let b = after.and_then(|p| p.get(name)).or(new_props.get(name));
+            if let (Some(a), Some(b)) = (a, b) {
+                let mut found = Vec::new();
+                compare(a, b, &join_path(path, name), &mut found);
+                out.extend(found.into_iter().map(|mut c| {
+                    c.message = format!("{} when {}", c.message, when);
+                    c
+                }));
+            }
+        }
+    }
+
+    if old.get("additionalProperties") != Some(&Value::Bool(false))
+        && new.get("additionalProperties") == Some(&Value::Bool(false))
+    {
+        push(out, true, path, "no longer allows additional properties".to_string());
+    }
+
+    if let (Some(a), Some(b)) = (old.get("items"), new.get("items")) {
+        compare(a, b, &format!("{path}[]"), out);
+    }
+}
+
+fn push(out: &mut Vec<Change>, breaking: bool, path: &str, message: String) {
+    out.push(Change {
+        breaking,
+        path: path.to_string(),
+        message,
+    });
+}
+
+fn types(schema: &Value) -> Vec<String> {
+    let mut t: Vec<String> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.clone()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).map(String::from).collect(),
+        _ => Vec::new(),
+    };
+    t.sort();
+    t
+}
+
+fn type_list(types: &[String]) -> String {
+    if types.is_empty() {
+        "any".to_string()
+    } else {
+        types.join(" or ")
+    }
+}
+
+fn values(v: Option<&Value>) -> Option<Vec<Value>> {
+    v.and_then(Value::as_array).cloned()
+}
+
+fn props(schema: &Value) -> BTreeMap<String, Value> {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|p| p.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+fn required(schema: &Value) -> Vec<String> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Fields required by `allOf` `if`/`then` clauses, keyed by a description of
+/// the condition, e.g. `` `action` is "divide" ``.
+fn conditional_required(schema: &Value) -> BTreeMap<String, Vec<String>> {
+    let mut out: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let clauses = schema.get("allOf").and_then(Value::as_array);
+    for clause in clauses.into_iter().flatten() {
+        let (Some(cond), Some(then)) = (clause.get("if"), clause.get("then")) else {
+            continue;
+        };
+        let when = describe_condition(cond).unwrap_or_default();
+        out.entry(when).or_default().extend(required(then));
+    }
+    out
+}
+
+/// Property schemas that `allOf` `if`/`then` clauses apply under their
+/// condition, keyed like [`conditional_required`].
+fn conditional_properties(schema: &Value) -> BTreeMap<String, BTreeMap<String, Value>> {
+    let mut out: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    let clauses = schema.get("allOf").and_then(Value::as_array);
+    for clause in clauses.into_iter().flatten() {
+        let (Some(cond), Some(then)) = (clause.get("if"), clause.get("then")) else {
+            continue;
+        };
+        let when = describe_condition(cond).unwrap_or_default();
+        out.entry(when).or_default().extend(props(then));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Prop, SchemaBuilder};
+
+    fn calculator() -> SchemaBuilder {
+        SchemaBuilder::new(&["add", "divide"])
+            .number("a", "", true)
+            .number("b", "", true)
+    }
+
+    fn breaking(changes: &[Change]) -> Vec<String> {
+        changes.iter().filter(|c| c.breaking).map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        assert!(diff(&calculator().build(), &calculator().build()).is_empty());
+    }
+
+    #[test]
+    fn removed_action_is_breaking() {
+        let new = SchemaBuilder::new(&["add"]).number("a", "", true).number("b", "", true);
+        let changes = diff(&calculator().build(), &new.build());
+        assert_eq!(breaking(&changes), ["breaking: `action` action \"divide\" was removed"]);
+    }
+
+    #[test]
+    fn added_action_is_compatible() {
+        let new = SchemaBuilder::new(&["add", "divide", "multiply"])
+            .number("a", "", true)
+            .number("b", "", true);
+        let changes = diff(&calculator().build(), &new.build());
+        assert!(!is_breaking(&changes));
+        assert_eq!(changes[0].to_string(), "compatible: `action` action \"multiply\" was added");
+    }
+
+    #[test]
+    fn newly_required_fields_are_breaking() {
+        let old = calculator().string("note", "", false).build();
+        let new = calculator().string("note", "", true).integer("scale", "", true).build();
+        assert_eq!(
+            breaking(&diff(&old, &new)),
+            [
+                "breaking: `scale` was added as a required property",
+                "breaking: `note` is now required",
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_newly_required_for_one_action_are_breaking() {
+        let old = SchemaBuilder::new(&["add", "divide"]).number("a", "", true).number("b", "", false);
+        let new = SchemaBuilder::new(&["add", "divide"])
+            .number("a", "", true)
+            .number("b", "", false)
+            .number("c", "", false)
+            .action_requires("divide", &["b", "c"]);
+        assert_eq!(
+            breaking(&diff(&old.build(), &new.build())),
+            [
+                "breaking: `b` is now required when `action` is \"divide\"",
+                "breaking: `c` was added as a required property when `action` is \"divide\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn optional_field_is_compatible() {
+        let changes = diff(&calculator().build(), &calculator().string("note", "", false).build());
+        assert!(!is_breaking(&changes));
+        assert_eq!(changes[0].to_string(), "compatible: `note` was added");
+    }
+
+    #[test]
+    fn narrowed_type_is_breaking() {
+        let old = calculator().build();
+        let new = SchemaBuilder::new(&["add", "divide"])
+            .integer("a", "", true)
+            .number("b", "", true)
+            .build();
+        assert_eq!(
+            breaking(&diff(&old, &new)),
+            ["breaking: `a` type changed from number to integer"]
+        );
+        assert!(!is_breaking(&diff(&new, &old)));
+    }
+
+    #[test]
+    fn removed_property_and_tightened_bound_are_breaking() {
+        let old = SchemaBuilder::new(&[])
+            .property("limit", Prop::integer().maximum(100), false)
+            .string("cursor", "", false)
+            .build();
+        let new = SchemaBuilder::new(&[])
+            .property("limit", Prop::integer().maximum(50), false)
+            .build();
+        assert_eq!(
+            breaking(&diff(&old, &new)),
+            [
+                "breaking: `cursor` was removed",
+                "breaking: `limit` `maximum` tightened from 100 to 50",
+            ]
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! Render tools in the tool-definition formats of other LLM platforms.
+//!
+//! Nebo schemas use JSON Schema features some targets reject, so each format
+//! down-converts what it cannot express:
+//!
+//! - [`Format::OpenAi`] and [`Format::Anthropic`] drop `allOf`/`if`/`then`
+//!   clauses and note per-action requirements in the property descriptions.
+//! - [`Format::OpenAiStrict`] additionally makes every property required
+//!   (optional ones become nullable), closes objects with
+//!   `additionalProperties: false`, and moves unsupported keywords such as
+//!   `minLength` or `default` into descriptions.
+//! - [`Format::Mcp`] keeps the schema as is.
+//!
+//! Tool names are sanitized to the characters and length each platform allows.
+//!
+//! ```no_run
+//! # use nebo_sdk::schema::export::{export_registry, Format};
+//! # let tools = nebo_sdk::ToolRegistry::new();
+//! let definitions = export_registry(&tools, Format::Anthropic);
+//! println!("{}", serde_json::to_string_pretty(&definitions).unwrap());
+//! ```
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::registry::ToolRegistry;
+use crate::schema::describe_condition;
+use crate::tool::ToolHandler;
+
+/// A target tool-definition format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// OpenAI function tool (`{"type": "function", "function": {...}}`).
+    OpenAi,
+    /// OpenAI function tool with `strict: true` structured outputs.
+    OpenAiStrict,
+    /// Anthropic Messages API tool (`name`, `description`, `input_schema`).
+    Anthropic,
+    /// MCP tool descriptor as returned by `tools/list`.
+    Mcp,
+}
+
+/// Render one tool in `format`.
+pub fn export(tool: &dyn ToolHandler, format: Format) -> Value {
+    let schema = tool.schema();
+    match format {
+        Format::OpenAi => json!({
+            "type": "function",
+            "function": {
+                "name": sanitize_name(tool.name(), 64),
+                "description": tool.description(),
+                "parameters": drop_conditionals(schema),
+            },
+        }),
+        Format::OpenAiStrict => json!({
+            "type": "function",
+            "function": {
+                "name": sanitize_name(tool.name(), 64),
+                "description": tool.description(),
+                "parameters": strict(drop_conditionals(schema)),
+                "strict": true,
+            },
+        }),
+        Format::Anthropic => json!({
+            "name": sanitize_name(tool.name(), 128),
+            "description": tool.description(),
+            "input_schema": drop_conditionals(schema),
+        }),
+        Format::Mcp => json!({
+            "name": tool.name(),
+            "description": tool.description(),
+            "inputSchema": schema,
+        }),
+    }
+}
+
+/// Render every tool in a registry in `format`.
+pub fn export_registry(tools: &ToolRegistry, format: Format) -> Vec<Value> {
+    tools.iter().map(|t| export(t.as_ref(), format)).collect()
+}
+
+/// Replace characters outside `[A-Za-z0-9_-]` with `_` and truncate.
+fn sanitize_name(name: &str, max: usize) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(max)
+        .collect()
+}
+
+/// Remove `allOf` and `if`/`then`/`else` from every object schema. Fields an
+/// `if`/`then` clause requires for particular actions are annotated in their
+/// descriptions; other `allOf` members are merged into the parent.
+fn drop_conditionals(schema: Value) -> Value {
+    let Value::Object(mut obj) = schema else {
+        return schema;
+    };
+    obj.remove("if");
+    obj.remove("then");
+    obj.remove("else");
+
+    // field -> conditions under which it is required
+    let mut conditional: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // field -> notes on the type it must have under some condition
+    let mut scoped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if let Some(Value::Array(all)) = obj.remove("allOf") {
+        for clause in all {
+            match (clause.get("if"), clause.get("then")) {
+                (Some(cond), Some(then)) => {
+                    let when = describe_condition(cond)
+                        .unwrap_or_else(|| "a condition holds".to_string());
+                    for field in string_list(then.get("required")) {
+                        conditional.entry(field).or_default().push(when.clone());
+                    }
+                    if let Some(Value::Object(props)) = then.get("properties") {
+                        for (field, sub) in props {
+                            let note = format!("When {when}: {}.", type_names(sub));
+                            scoped.entry(field.clone()).or_default().push(note);
+                        }
+                    }
+                }
+                _ => merge_into(&mut obj, drop_conditionals(clause)),
+            }
+        }
+    }
+
+    if let Some(Value::Object(props)) = obj.get_mut("properties") {
+        for (field, whens) in conditional {
+            if let Some(Value::Object(prop)) = props.get_mut(&field) {
+                append_description(prop, &format!("Required when {}.", whens.join(" or ")));
+            }
+        }
+        for (field, notes) in scoped {
+            if let Some(Value::Object(prop)) = props.get_mut(&field) {
+                append_description(prop, &notes.join(" "));
+            }
+        }
+    }
+
+    map_children(obj, drop_conditionals)
+}
+
+/// Convert a schema to OpenAI strict mode.
+fn strict(schema: Value) -> Value {
+    let Value::Object(mut obj) = schema else {
+        return schema;
+    };
+
+    for keyword in ["minLength", "maxLength", "uniqueItems", "default", "examples"] {
+        if let Some(v) = obj.remove(keyword) {
+            let note = match keyword {
+                "minLength" => format!("At least {v} characters."),
+                "maxLength" => format!("At most {v} characters."),
+                "uniqueItems" => "Items must be unique.".to_string(),
+                "default" => format!("Defaults to {v}."),
+                _ => format!("Examples: {}.", list(&v)),
+            };
+            append_description(&mut obj, &note);
+        }
+    }
+
+    let has_props = obj.contains_key("properties");
+    if has_props {
+        let required = string_list(obj.get("required"));
+        if let Some(Value::Object(props)) = obj.get_mut("properties") {
+            for (name, prop) in props.iter_mut() {
+                if !required.contains(name) {
+                    make_nullable(prop);
+                }
+            }
+            let all: Vec<Value> = props.keys().cloned().map(Value::String).collect();
+            obj.insert("required".to_string(), Value::Array(all));
+        }
+        obj.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+
+    map_children(obj, strict)
+}
+
+/// Apply `f` to the schemas of nested properties and array items.
+fn map_children(mut obj: Map<String, Value>, f: fn(Value) -> Value) -> Value {
+    if let Some(Value::Object(props)) = obj.get_mut("properties") {
+        for prop in props.values_mut() {
+            *prop = f(prop.take());
+        }
+    }
+    if let Some(items) = obj.get_mut("items") {
+        *items = f(items.take());
+    }
+    if let Some(extra @ Value::Object(_)) = obj.get_mut("additionalProperties") {
+        *extra = f(extra.take());
+    }
+    Value::Object(obj)
+}
+
+fn make_nullable(prop: &mut Value) {
+    let Some(obj) = prop.as_object_mut() else {
+        return;
+    };
+    match obj.get_mut("type") {
+        Some(Value::String(t)) if t != "null" => {
+            let t = std::mem::take(t);
+            obj.insert("type".to_string(), json!([t, "null"]));
+        }
+        Some(Value::Array(types)) if !types.contains(&json!("null")) => types.push(json!("null")),
+        _ => {}
+    }
+    if let Some(Value::Array(values)) = obj.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+}
+
+fn merge_into(obj: &mut Map<String, Value>, other: Value) {
+    let Value::Object(other) = other else {
+        return;
+    };
+    if let Some(Value::Object(extra)) = other.get("properties") {
+        let props = obj
+            .entry("properties")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(props) = props {
+            for (k, v) in extra {
+                props.entry(k.clone()).or_insert_with(|| v.clone());
+            }
+        }
+    }
+    let extra = string_list(other.get("required"));
+    if !extra.is_empty() {
+        let mut required = string_list(obj.get("required"));
+        for r in extra {
+            if !required.contains(&r) {
+                required.push(r);
+            }
+        }
+        obj.insert("required".to_string(), json!(required));
+    }
+}
+
+fn append_description(obj: &mut Map<String, Value>, note: &str) {
+    let text = match obj.get("description").and_then(Value::as_str) {
+        Some(d) if d.ends_with(['.', '!', '?']) => format!("{d} {note}"),
+        Some(d) if !d.is_empty() => format!("{d}. {note}"),
+        _ => note.to_string(),
+    };
+    obj.insert("description".to_string(), Value::String(text));
+}
+
+fn string_list(v: Option<&Value>) -> Vec<String> {
+    v.and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// The types a schema allows, e.g. `number or string`.
+fn type_names(schema: &Value) -> String {
+    match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(_)) => string_list(schema.get("type")).join(" or "),
+        _ => "any value".to_string(),
+    }
+}
+
+fn list(v: &Value) -> String {
+    match v {
+        Value::Array(items) => items.iter().map(Value::to_string).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{strap_schema, Prop, SchemaBuilder};
+    use crate::tool_fn;
+
+    fn tool(name: &str, schema: Value) -> impl ToolHandler {
+        tool_fn(name, "Write or delete files.", schema, |_, _ctx| async move { Ok("") })
+    }
+
+    /// A STRAP tool with a per-action requirement, a nested object and
+    /// keywords strict mode does not support.
+    fn files() -> impl ToolHandler {
+        let options = SchemaBuilder::new(&[]).boolean("backup", "Keep a backup", false);
+        let schema = SchemaBuilder::new(&["write", "delete"])
+            .property("path", Prop::string().description("File path").min_length(1), true)
+            .property("content", Prop::string().description("New contents").max_length(4096), false)
+            .property("mode", Prop::enum_of(&["replace", "append"]).default("replace"), false)
+            .object("options", "Write options.", false, options)
+            .array("tags", "Labels to set", false, Prop::string())
+            .action_requires("write", &["content"])
+            .build();
+        tool("files.write v2", schema)
+    }
+
+    /// The `files` schema without conditionals, as OpenAI and Anthropic get it.
+    fn files_without_conditionals() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["write", "delete"],
+                    "description": "Action to perform: write, delete",
+                },
+                "path": {"type": "string", "description": "File path", "minLength": 1},
+                "content": {
+                    "type": "string",
+                    "description": "New contents. Required when `action` is \"write\".",
+                    "maxLength": 4096,
+                },
+                "mode": {"type": "string", "enum": ["replace", "append"], "default": "replace"},
+                "options": {
+                    "type": "object",
+                    "description": "Write options.",
+                    "properties": {"backup": {"type": "boolean", "description": "Keep a backup"}},
+                    "required": [],
+                },
+                "tags": {
+                    "type": "array",
+                    "description": "Labels to set",
+                    "items": {"type": "string"},
+                },
+            },
+            "required": ["action", "path"],
+        })
+    }
+
+    #[test]
+    fn openai() {
+        assert_eq!(
+            export(&files(), Format::OpenAi),
+            json!({
+                "type": "function",
+                "function": {
+                    "name": "files_write_v2",
+                    "description": "Write or delete files.",
+                    "parameters": files_without_conditionals(),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn anthropic() {
+        assert_eq!(
+            export(&files(), Format::Anthropic),
+            json!({
+                "name": "files_write_v2",
+                "description": "Write or delete files.",
+                "input_schema": files_without_conditionals(),
+            })
+        );
+    }
+
+    #[test]
+    fn openai_strict() {
+        assert_eq!(
+            export(&files(), Format::OpenAiStrict),
+            json!({
+                "type": "function",
+                "function": {
+                    "name": "files_write_v2",
+                    "description": "Write or delete files.",
+                    "strict": true,
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "action": {
+                                "type": "string",
+                                "enum": ["write", "delete"],
+                                "description": "Action to perform: write, delete",
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "File path. At least 1 characters.",
+                            },
+                            "content": {
+                                "type": ["string", "null"],
+                                "description": "New contents. Required when `action` is \"write\". \
+                                                At most 4096 characters.",
+                            },
+                            "mode": {
+                                "type": ["string", "null"],
+                                "enum": ["replace", "append", null],
+                                "description": "Defaults to \"replace\".",
+                            },
+                            "options": {
+                                "type": ["object", "null"],
+                                "description": "Write options.",
+                                "properties": {
+                                    "backup": {
+                                        "type": ["boolean", "null"],
+                                        "description": "Keep a backup",
+                                    },
+                                },
+                                "required": ["backup"],
+                                "additionalProperties": false,
+                            },
+                            "tags": {
+                                "type": ["array", "null"],
+                                "description": "Labels to set",
+                                "items": {"type": "string"},
+                            },
+                        },
+                        "required": ["action", "content", "mode", "options", "path", "tags"],
+                        "additionalProperties": false,
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn mcp_keeps_the_schema() {
+        assert_eq!(
+            export(&files(), Format::Mcp),
+            json!({
+                "name": "files.write v2",
+                "description": "Write or delete files.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "action": {
+                            "type": "string",
+                            "enum": ["write", "delete"],
+                            "description": "Action to perform: write, delete",
+                        },
+                        "path": {"type": "string", "description": "File path", "minLength": 1},
+                        "content": {
+                            "type": "string",
+                            "description": "New contents",
+                            "maxLength": 4096,
+                        },
+                        "mode": {
+                            "type": "string",
+                            "enum": ["replace", "append"],
+                            "default": "replace",
+                        },
+                        "options": {
+                            "type": "object",
+                            "description": "Write options.",
+                            "properties": {
+                                "backup": {"type": "boolean", "description": "Keep a backup"},
+                            },
+                            "required": [],
+                        },
+                        "tags": {
+                            "type": "array",
+                            "description": "Labels to set",
+                            "items": {"type": "string"},
+                        },
+                    },
+                    "required": ["action", "path"],
+                    "allOf": [{
+                        "if": {
+                            "properties": {"action": {"const": "write"}},
+                            "required": ["action"],
+                        },
+                        "then": {"required": ["content"]},
+                    }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn per_action_types_become_notes_and_plain_clauses_merge() {
+        let resize = SchemaBuilder::new(&[]).integer("size", "New size", true).build();
+        let label = SchemaBuilder::new(&[])
+            .property("size", Prop::string().description("Size label"), false)
+            .string("text", "Label text", true)
+            .build();
+        let mut schema = strap_schema(
+            "action",
+            vec![("resize".to_string(), resize), ("label".to_string(), label)],
+        );
+        schema["allOf"].as_array_mut().unwrap().push(json!({
+            "properties": {"dry_run": {"type": "boolean"}},
+            "required": ["dry_run"],
+        }));
+        assert_eq!(
+            drop_conditionals(schema),
+            json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["resize", "label"],
+                        "description": "Action to perform: resize, label",
+                    },
+                    "size": {
+                        "type": ["integer", "string"],
+                        "description": "New size. Required when `action` is \"resize\". \
+                                        When `action` is \"resize\": integer. \
+                                        When `action` is \"label\": string.",
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Label text. Required when `action` is \"label\".",
+                    },
+                    "dry_run": {"type": "boolean"},
+                },
+                "required": ["action", "dry_run"],
+            })
+        );
+    }
+
+    #[test]
+    fn names_are_sanitized_per_platform() {
+        let name = format!("café.{}", "x".repeat(200));
+        let schema = json!({"type": "object"});
+        let openai = export(&tool(&name, schema.clone()), Format::OpenAi);
+        let anthropic = export(&tool(&name, schema.clone()), Format::Anthropic);
+        let mcp = export(&tool(&name, schema), Format::Mcp);
+        assert_eq!(openai["function"]["name"], format!("caf__{}", "x".repeat(59)));
+        assert_eq!(anthropic["name"], format!("caf__{}", "x".repeat(123)));
+        assert_eq!(mcp["name"], name);
+        assert_eq!(sanitize_name("web-search_2", 64), "web-search_2");
+    }
+}
@@ -0,0 +1,320 @@
+//! Static checks for tool definitions.
+//!
+//! [`lint`] reports names that are not valid identifiers, missing or
+//! oversized descriptions, and schemas that are not well-formed JSON Schema.
+//! Errors make a tool unusable on some hosts; warnings are worth fixing.
+//! With the `testing` feature, `nebo_sdk::testing::assert_lint_clean` fails a
+//! test on any error.
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::registry::ToolRegistry;
+use crate::tool::ToolHandler;
+
+const MAX_NAME_LEN: usize = 64;
+const MIN_DESCRIPTION_LEN: usize = 10;
+const MAX_DESCRIPTION_LEN: usize = 1024;
+const TYPES: [&str; 7] = ["string", "number", "integer", "boolean", "object", "array", "null"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found in a tool definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub severity: Severity,
+    /// What the issue is about: `name`, `description`, or a schema location
+    /// such as `schema.properties.limit`. Prefixed with the tool name when
+    /// linting a registry.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: `{}` {}", level, self.path, self.message)
+    }
+}
+
+/// Lint a tool's name, description and schema.
+pub fn lint(tool: &dyn ToolHandler) -> Vec<LintIssue> {
+    let mut out = Vec::new();
+    let name = tool.name();
+    let valid_name = name.len() <= MAX_NAME_LEN
+        && name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        out.push(issue(
+            Severity::Error,
+            "name",
+            format!(
+                "{name:?} must start with a letter and contain only letters, digits, `_` or `-` (at most {MAX_NAME_LEN})"
+            ),
+        ));
+    }
+
+    let description = tool.description().trim();
+    if description.is_empty() {
+        out.push(issue(Severity::Error, "description", "is empty".to_string()));
+    } else if description.chars().count() < MIN_DESCRIPTION_LEN {
+        out.push(issue(
+            Severity::Warning,
+            "description",
+            "is too short to tell the model when to use the tool".to_string(),
+        ));
+    } else if description.chars().count() > MAX_DESCRIPTION_LEN {
+        out.push(issue(
+            Severity::Warning,
+            "description",
+            format!("is longer than {MAX_DESCRIPTION_LEN} characters"),
+        ));
+    }
+
+    out.extend(lint_schema(&tool.schema()));
+    out
+}
+
+/// Lint every tool in a registry, including duplicate names.
+pub fn lint_registry(tools: &ToolRegistry) -> Vec<LintIssue> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    for tool in tools.iter() {
+        if !seen.insert(tool.name()) {
+            out.push(issue(Severity::Error, tool.name(), "is registered twice".to_string()));
+        }
+        out.extend(lint(tool.as_ref()).into_iter().map(|mut i| {
+            i.path = format!("{}.{}", tool.name(), i.path);
+            i
+        }));
+    }
+    out
+}
+
+/// Lint a tool input schema. The root must be an object schema.
+pub fn lint_schema(schema: &Value) -> Vec<LintIssue> {
+    let mut out = Vec::new();
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        out.push(issue(
+            Severity::Error,
+            "schema",
+            "must be an object schema (`\"type\": \"object\"`)".to_string(),
+        ));
+    }
+    if let Some(action) = schema.get("properties").and_then(|p| p.get("action")) {
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .is_some_and(|r| r.contains(&Value::from("action")));
+        if action.get("enum").is_none() || !required {
+            out.push(issue(
+                Severity::Warning,
+                "schema.properties.action",
+                "should be a required enum of action names (STRAP)".to_string(),
+            ));
+        }
+    }
+    check(schema, "schema", &mut out);
+    out
+}
+
+fn check(schema: &Value, path: &str, out: &mut Vec<LintIssue>) {
+    let Some(obj) = schema.as_object() else {
+        if !schema.is_boolean() {
+            out.push(issue(Severity::Error, path, "is not a schema object".to_string()));
+        }
+        return;
+    };
+    let mut error = |message: String| out.push(issue(Severity::Error, path, message));
+
+    match obj.get("type") {
+        None => {}
+        Some(Value::String(t)) if TYPES.contains(&t.as_str()) => {}
+        Some(Value::Array(ts))
+            if !ts.is_empty() && ts.iter().all(|t| t.as_str().is_some_and(|t| TYPES.contains(&t))) => {}
+        Some(t) => error(format!("has invalid type {t}")),
+    }
+
+    if let Some(values) = obj.get("enum") {
+        if values.as_array().is_none_or(|v| v.is_empty()) {
+            error("`enum` must be a non-empty array".to_string());
+        }
+    }
+
+    if let Some(pattern) = obj.get("pattern") {
+        match pattern.as_str() {
+            Some(p) if regex::Regex::new(p).is_err() => {
+                error(format!("`pattern` {p:?} is not a valid regular expression"))
+            }
+            None => error("`pattern` must be a string".to_string()),
+            _ => {}
+        }
+    }
+
+    for (min, max) in [
+        ("minimum", "maximum"),
+        ("minLength", "maxLength"),
+        ("minItems", "maxItems"),
+    ] {
+        let lo = obj.get(min).and_then(Value::as_f64);
+        let hi = obj.get(max).and_then(Value::as_f64);
+        if let (Some(lo), Some(hi)) = (lo, hi) {
+            if lo > hi {
+                error(format!("`{min}` ({lo}) is greater than `{max}` ({hi})"));
+            }
+        }
+    }
+
+    let props = obj.get("properties");
+    if let Some(p) = props {
+        if !p.is_object() {
+            error("`properties` must be an object".to_string());
+        }
+    }
+    // Fragments without a `type` (e.g. `then` clauses) may require
+    // properties declared by the parent.
+    let own_props = props.filter(|_| obj.contains_key("type"));
+    if let Some(required) = obj.get("required") {
+        match required.as_array() {
+            Some(names) => {
+                for name in names {
+                    match name.as_str() {
+                        Some(n) if own_props.is_some_and(|p| p.get(n).is_none()) => {
+                            error(format!("requires `{n}`, which is not in `properties`"))
+                        }
+                        None => error(format!("`required` entry {name} is not a string")),
+                        _ => {}
+                    }
+                }
+            }
+            None => error("`required` must be an array".to_string()),
+        }
+    }
+
+    if let Some(Value::Object(p)) = props {
+        for (name, sub) in p {
+            check(sub, &format!("{path}.properties.{name}"), out);
+        }
+    }
+    if let Some(items) = obj.get("items") {
+        check(items, &format!("{path}.items"), out);
+    }
+    if let Some(extra @ Value::Object(_)) = obj.get("additionalProperties") {
+        check(extra, &format!("{path}.additionalProperties"), out);
+    }
+    if let Some(all) = obj.get("allOf") {
+        match all.as_array() {
+            Some(all) => {
+                for (i, sub) in all.iter().enumerate() {
+                    check(sub, &format!("{path}.allOf[{i}]"), out);
+                }
+            }
+            None => out.push(issue(Severity::Error, path, "`allOf` must be an array".to_string())),
+        }
+    }
+    for keyword in ["if", "then", "else"] {
+        if let Some(sub) = obj.get(keyword) {
+            check(sub, &format!("{path}.{keyword}"), out);
+        }
+    }
+}
+
+fn issue(severity: Severity, path: &str, message: String) -> LintIssue {
+    LintIssue {
+        severity,
+        path: path.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaBuilder;
+    use crate::tool::tool_fn;
+    use serde_json::json;
+
+    fn messages(issues: &[LintIssue]) -> Vec<String> {
+        issues.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn clean_tool_has_no_issues() {
+        let schema = SchemaBuilder::new(&["add", "divide"])
+            .number("a", "First operand", true)
+            .number("b", "Second operand", false)
+            .action_requires("divide", &["b"])
+            .build();
+        let tool = tool_fn("calculator", "Performs arithmetic.", schema, |_, _| async { Ok("") });
+        assert!(lint(&tool).is_empty());
+    }
+
+    #[test]
+    fn bad_name_and_description() {
+        let schema = SchemaBuilder::new(&[]).build();
+        let tool = tool_fn("1 calc", "", schema.clone(), |_, _| async { Ok("") });
+        assert_eq!(
+            messages(&lint(&tool)),
+            [
+                "error: `name` \"1 calc\" must start with a letter and contain only letters, digits, `_` or `-` (at most 64)",
+                "error: `description` is empty",
+            ]
+        );
+        let tool = tool_fn("calc", "Math", schema, |_, _| async { Ok("") });
+        assert_eq!(lint(&tool)[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn malformed_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": {"type": "float"},
+                "b": {"type": "string", "pattern": "("},
+                "c": {"type": "integer", "minimum": 5, "maximum": 1},
+                "d": {"type": "string", "enum": []},
+            },
+            "required": ["a", "missing"],
+        });
+        assert_eq!(
+            messages(&lint_schema(&schema)),
+            [
+                "error: `schema` requires `missing`, which is not in `properties`",
+                "error: `schema.properties.a` has invalid type \"float\"",
+                "error: `schema.properties.b` `pattern` \"(\" is not a valid regular expression",
+                "error: `schema.properties.c` `minimum` (5) is greater than `maximum` (1)",
+                "error: `schema.properties.d` `enum` must be a non-empty array",
+            ]
+        );
+    }
+
+    #[test]
+    fn root_must_be_object_and_action_an_enum() {
+        let issues = lint_schema(&json!({"type": "array"}));
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[0].path, "schema");
+
+        let schema = json!({"type": "object", "properties": {"action": {"type": "string"}}});
+        assert_eq!(
+            messages(&lint_schema(&schema)),
+            ["warning: `schema.properties.action` should be a required enum of action names (STRAP)"]
+        );
+    }
+
+    #[test]
+    fn duplicate_tools_in_registry() {
+        let schema = SchemaBuilder::new(&[]).build();
+        let tools = ToolRegistry::new()
+            .register(tool_fn("calc", "Performs arithmetic.", schema.clone(), |_, _| async { Ok("") }))
+            .register(tool_fn("calc", "Performs arithmetic.", schema, |_, _| async { Ok("") }));
+        assert_eq!(messages(&lint_registry(&tools)), ["error: `calc` is registered twice"]);
+    }
+}
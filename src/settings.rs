@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::de::value::{Error as DeError, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+use tokio::sync::watch;
+
+use crate::error::NeboError;
+
+/// Raw settings values as delivered by Nebo's `Configure` RPC.
+pub type SettingsValues = HashMap<String, String>;
+
+pub(crate) type ConfigureCallback = Box<dyn Fn(SettingsValues) + Send + Sync>;
+pub(crate) type SettingsValidator = Box<dyn Fn(&SettingsValues) -> Result<(), NeboError> + Send + Sync>;
+
+/// Shared settings store, updated by every capability's `Configure` RPC.
+///
+/// Cloning is cheap; all clones observe the same values. Handlers typically
+/// grab a clone via [`NeboApp::settings`](crate::NeboApp::settings) before
+/// registration and either read a snapshot or await changes with
+/// [`Settings::subscribe`].
+#[derive(Clone)]
+pub struct Settings {
+    inner: Arc<SettingsInner>,
+}
+
+struct SettingsInner {
+    tx: watch::Sender<SettingsValues>,
+    validators: Mutex<Vec<SettingsValidator>>,
+    on_configure: Mutex<Option<ConfigureCallback>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Settings {
+    /// Create an empty settings store.
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(SettingsValues::new());
+        Self {
+            inner: Arc::new(SettingsInner {
+                tx,
+                validators: Mutex::new(Vec::new()),
+                on_configure: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns a snapshot of the current settings.
+    pub fn values(&self) -> SettingsValues {
+        self.inner.tx.borrow().clone()
+    }
+
+    /// Returns a single setting, if present.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.inner.tx.borrow().get(key).cloned()
+    }
+
+    /// Deserialize the current settings into a typed struct.
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, NeboError> {
+        parse(&self.inner.tx.borrow())
+    }
+
+    /// Subscribe to settings updates. The receiver starts with the current
+    /// values marked as seen; `changed().await` resolves on the next update.
+    pub fn subscribe(&self) -> watch::Receiver<SettingsValues> {
+        self.inner.tx.subscribe()
+    }
+
+    pub(crate) fn add_validator(&self, v: SettingsValidator) {
+        self.inner.validators.lock().unwrap().push(v);
+    }
+
+    pub(crate) fn set_on_configure(&self, cb: ConfigureCallback) {
+        *self.inner.on_configure.lock().unwrap() = Some(cb);
+    }
+
+    /// Validate and store new values, then notify subscribers and the
+    /// `on_configure` callback. Rejected values leave the store unchanged.
+    pub(crate) fn apply(&self, values: SettingsValues) -> Result<(), NeboError> {
+        for v in self.inner.validators.lock().unwrap().iter() {
+            v(&values)?;
+        }
+        self.inner.tx.send_replace(values.clone());
+        if let Some(ref cb) = *self.inner.on_configure.lock().unwrap() {
+            cb(values);
+        }
+        Ok(())
+    }
+}
+
+/// Deserialize a settings map into a typed struct.
+///
+/// Values are strings on the wire; they are parsed on demand into the field's
+/// type (`"true"` into `bool`, `"8080"` into `u16`, `"a,b"` into `Vec<String>`).
+/// Empty strings deserialize to `None` for `Option` fields.
+pub fn parse<T: DeserializeOwned>(values: &SettingsValues) -> Result<T, NeboError> {
+    let map = MapDeserializer::<_, DeError>::new(
+        values.iter().map(|(k, v)| (k.clone(), SettingValue(v.clone()))),
+    );
+    T::deserialize(map).map_err(|e| NeboError::InvalidSettings(e.to_string()))
+}
+
+struct SettingValue(String);
+
+impl<'de> IntoDeserializer<'de, DeError> for SettingValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident : $ty:ty),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                match self.0.trim().parse::<$ty>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for SettingValue {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => visitor.visit_bool(true),
+            "false" | "0" | "no" | "off" | "" => visitor.visit_bool(false),
+            _ => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let items: Vec<SettingValue> = self
+            .0
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| SettingValue(s.to_string()))
+            .collect();
+        visitor.visit_seq(SeqDeserializer::new(items.into_iter()))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn values(pairs: &[(&str, &str)]) -> SettingsValues {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Safe,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        host: String,
+        port: u16,
+        verbose: bool,
+        ratio: f64,
+        tags: Vec<String>,
+        token: Option<String>,
+        mode: Mode,
+        #[serde(default)]
+        retries: u32,
+    }
+
+    #[test]
+    fn parses_strings_into_field_types() {
+        let config: Config = parse(&values(&[
+            ("host", "example.com"),
+            ("port", " 8080 "),
+            ("verbose", "Yes"),
+            ("ratio", "0.5"),
+            ("tags", "a, b,,c"),
+            ("token", ""),
+            ("mode", "safe"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                host: "example.com".into(),
+                port: 8080,
+                verbose: true,
+                ratio: 0.5,
+                tags: vec!["a".into(), "b".into(), "c".into()],
+                token: None,
+                mode: Mode::Safe,
+                retries: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_invalid_and_missing_values() {
+        let base = [
+            ("host", "h"),
+            ("port", "80"),
+            ("verbose", "off"),
+            ("ratio", "1"),
+            ("tags", ""),
+            ("mode", "fast"),
+        ];
+        assert!(parse::<Config>(&values(&base)).is_ok());
+
+        let mut bad = values(&base);
+        bad.insert("port".into(), "eighty".into());
+        let err = parse::<Config>(&bad).unwrap_err().to_string();
+        assert!(err.contains("eighty"), "{err}");
+
+        let mut bad = values(&base);
+        bad.insert("verbose".into(), "maybe".into());
+        assert!(matches!(parse::<Config>(&bad), Err(NeboError::InvalidSettings(_))));
+
+        let mut missing = values(&base);
+        missing.remove("host");
+        let err = parse::<Config>(&missing).unwrap_err().to_string();
+        assert!(err.contains("host"), "{err}");
+    }
+
+    #[test]
+    fn apply_notifies_subscribers_and_callback() {
+        let settings = Settings::new();
+        let mut rx = settings.subscribe();
+        let seen = Arc::new(Mutex::new(None));
+        let sink = seen.clone();
+        settings.set_on_configure(Box::new(move |v| *sink.lock().unwrap() = Some(v)));
+
+        settings.apply(values(&[("key", "v1")])).unwrap();
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(rx.borrow_and_update().get("key").unwrap(), "v1");
+        assert_eq!(settings.get("key").as_deref(), Some("v1"));
+        assert_eq!(*seen.lock().unwrap(), Some(values(&[("key", "v1")])));
+    }
+
+    #[test]
+    fn rejected_values_leave_the_store_unchanged() {
+        let settings = Settings::new();
+        settings.add_validator(Box::new(|v| match v.get("key").map(String::as_str) {
+            Some("bad") => Err(NeboError::InvalidSettings("key must not be bad".into())),
+            _ => Ok(()),
+        }));
+        let mut rx = settings.subscribe();
+
+        settings.apply(values(&[("key", "good")])).unwrap();
+        rx.borrow_and_update();
+        let err = settings.apply(values(&[("key", "bad")])).unwrap_err();
+        assert!(matches!(err, NeboError::InvalidSettings(m) if m == "key must not be bad"));
+        assert_eq!(settings.values(), values(&[("key", "good")]));
+        assert!(!rx.has_changed().unwrap());
+    }
+}
@@ -0,0 +1,36 @@
+use tokio::signal;
+use tokio::sync::watch;
+
+/// Broadcasts the start of shutdown to streaming RPCs so they can close
+/// their streams and let the server drain.
+#[derive(Clone)]
+pub(crate) struct ShutdownSignal {
+    rx: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> (watch::Sender<bool>, Self) {
+        let (tx, rx) = watch::channel(false);
+        (tx, Self { rx })
+    }
+
+    /// Resolves once shutdown has begun.
+    pub async fn wait(&mut self) {
+        let _ = self.rx.wait_for(|stopping| *stopping).await;
+    }
+}
+
+/// Resolves on SIGTERM (sent by Nebo's sandbox) or SIGINT.
+pub(crate) async fn os_signal() {
+    let mut term = match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+        Ok(term) => term,
+        Err(_) => {
+            let _ = signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = signal::ctrl_c() => {}
+        _ = term.recv() => {}
+    }
+}
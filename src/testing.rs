@@ -0,0 +1,176 @@
+//! In-process test harness that serves a [`NeboApp`] on a temporary Unix
+//! socket and hands back gRPC clients for each capability service.
+//!
+//! Enabled with the `testing` feature:
+//!
+//! ```no_run
+//! # use nebo_sdk::{AppEnv, NeboApp};
+//! # use nebo_sdk::testing::TestApp;
+//! # async fn demo(tool: impl nebo_sdk::tool::ToolHandler) -> Result<(), nebo_sdk::NeboError> {
+//! let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool)).await?;
+//! let resp = app
+//!     .tool()
+//!     .execute(nebo_sdk::pb::ExecuteRequest {
+//!         input: br#"{"action":"add","a":1,"b":2}"#.to_vec(),
+//!         ..Default::default()
+//!     })
+//!     .await
+//!     .unwrap()
+//!     .into_inner();
+//! assert!(!resp.is_error);
+//! app.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use hyper_util::rt::TokioIo;
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+use crate::app::NeboApp;
+use crate::error::NeboError;
+use crate::pb;
+use crate::schema::lint::{lint, Severity};
+use crate::tool::ToolHandler;
+
+pub use pb::channel_service_client::ChannelServiceClient;
+pub use pb::comm_service_client::CommServiceClient;
+pub use pb::gateway_service_client::GatewayServiceClient;
+pub use pb::schedule_service_client::ScheduleServiceClient;
+pub use pb::tool_service_client::ToolServiceClient;
+pub use pb::ui_service_client::UiServiceClient;
+
+static NEXT_SOCKET: AtomicUsize = AtomicUsize::new(0);
+
+/// A running app under test. Shuts the app down when dropped.
+pub struct TestApp {
+    sock_path: PathBuf,
+    channel: Channel,
+    stop: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<Result<(), NeboError>>>,
+}
+
+impl TestApp {
+    /// Serve `app` on a fresh temporary socket and connect to it.
+    ///
+    /// Returns the app's startup error (e.g. [`NeboError::NoHandlers`]) if it
+    /// exits before accepting connections.
+    pub async fn start(mut app: NeboApp) -> Result<Self, NeboError> {
+        let sock_path = std::env::temp_dir().join(format!(
+            "nebo-test-{}-{}.sock",
+            std::process::id(),
+            NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+        ));
+        app.set_sock_path(sock_path.to_string_lossy().into_owned());
+
+        let (stop, stopped) = oneshot::channel::<()>();
+        let mut handle = tokio::spawn(app.run_until(async {
+            let _ = stopped.await;
+        }));
+
+        // Wait for the socket to accept connections, or for the app to fail.
+        loop {
+            if handle.is_finished() {
+                return match (&mut handle).await {
+                    Ok(Ok(())) => Err(NeboError::Other("app exited before serving".into())),
+                    Ok(Err(e)) => Err(e),
+                    Err(e) => Err(NeboError::Other(e.to_string())),
+                };
+            }
+            if UnixStream::connect(&sock_path).await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let path = sock_path.clone();
+        let channel = Endpoint::from_static("http://nebo.test")
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                let path = path.clone();
+                async move { UnixStream::connect(path).await.map(TokioIo::new) }
+            }))
+            .await?;
+
+        Ok(Self {
+            sock_path,
+            channel,
+            stop: Some(stop),
+            handle: Some(handle),
+        })
+    }
+
+    /// Path of the socket the app is serving on.
+    pub fn sock_path(&self) -> &Path {
+        &self.sock_path
+    }
+
+    pub fn tool(&self) -> ToolServiceClient<Channel> {
+        ToolServiceClient::new(self.channel.clone())
+    }
+
+    pub fn channel(&self) -> ChannelServiceClient<Channel> {
+        ChannelServiceClient::new(self.channel.clone())
+    }
+
+    pub fn gateway(&self) -> GatewayServiceClient<Channel> {
+        GatewayServiceClient::new(self.channel.clone())
+    }
+
+    pub fn ui(&self) -> UiServiceClient<Channel> {
+        UiServiceClient::new(self.channel.clone())
+    }
+
+    pub fn comm(&self) -> CommServiceClient<Channel> {
+        CommServiceClient::new(self.channel.clone())
+    }
+
+    pub fn schedule(&self) -> ScheduleServiceClient<Channel> {
+        ScheduleServiceClient::new(self.channel.clone())
+    }
+
+    /// Trigger a graceful shutdown and wait for the app to exit.
+    pub async fn shutdown(mut self) -> Result<(), NeboError> {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        match self.handle.take() {
+            Some(handle) => handle.await.map_err(|e| NeboError::Other(e.to_string()))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// Fail the test if [`lint`](crate::schema::lint::lint) reports any error for
+/// `tool`. Warnings are printed but do not fail.
+#[track_caller]
+pub fn assert_lint_clean(tool: &dyn ToolHandler) {
+    let issues = lint(tool);
+    for warning in issues.iter().filter(|i| i.severity == Severity::Warning) {
+        eprintln!("{}: {}", tool.name(), warning);
+    }
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .map(ToString::to_string)
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "tool `{}` has lint errors:\n  {}",
+        tool.name(),
+        errors.join("\n  ")
+    );
+}
@@ -1,10 +1,27 @@
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
 
+use crate::approval::{Approval, Preview};
+use crate::context::{ProgressSink, ToolContext};
 use crate::env::AppEnv;
 use crate::error::NeboError;
+use crate::jobs::Jobs;
+use crate::output::ToolOutput;
 use crate::pb;
+use crate::registry::ToolRegistry;
+use crate::schema::NeboSchema;
+use crate::settings::Settings;
+use crate::validate::check_input;
 
 /// Trait for tool capability handlers.
 #[async_trait]
@@ -12,16 +29,473 @@ pub trait ToolHandler: Send + Sync + 'static {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
     fn schema(&self) -> Value;
-    async fn execute(&self, input: Value) -> Result<String, NeboError>;
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError>;
+    /// Whether calls may need user confirmation. Hosts that do not ask
+    /// per call rely on this, so return `true` if any call could.
     fn requires_approval(&self) -> bool {
         false
     }
+    /// Decide whether this particular call needs user confirmation. Called
+    /// with validated input before `execute`; defaults to `requires_approval`.
+    async fn approval(&self, input: &Value, ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let _ = (input, ctx);
+        Ok(self.requires_approval().into())
+    }
+    /// Describe what `execute` would do with this input, without side
+    /// effects. Return `None` (the default) if the tool has no preview.
+    async fn preview(&self, input: &Value, ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let _ = (input, ctx);
+        Ok(None)
+    }
+    /// Whether `execute` reports through [`ToolContext::progress`]. Nebo uses
+    /// `ExecuteStream` for such tools and plain `Execute` otherwise.
+    fn reports_progress(&self) -> bool {
+        false
+    }
+    /// Maximum run time for one call. `None` (the default) falls back to
+    /// [`NeboApp::tool_timeout`](crate::NeboApp::tool_timeout).
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once after in-flight calls have drained on shutdown.
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+/// Tool handler whose input is deserialized into `Self::Input` by the SDK.
+///
+/// Every `TypedToolHandler` is a [`ToolHandler`]; its schema defaults to
+/// [`NeboSchema::nebo_schema`] of the input type, and input that fails to
+/// deserialize is reported back to the model without reaching `execute`.
+#[async_trait]
+pub trait TypedToolHandler: Send + Sync + 'static {
+    type Input: DeserializeOwned + NeboSchema + Send + Sync;
+
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn schema(&self) -> Value {
+        Self::Input::nebo_schema()
+    }
+    async fn execute(&self, input: Self::Input, ctx: &ToolContext)
+        -> Result<ToolOutput, NeboError>;
+    fn requires_approval(&self) -> bool {
+        false
+    }
+    async fn approval(
+        &self,
+        input: &Self::Input,
+        ctx: &ToolContext,
+    ) -> Result<Approval, NeboError> {
+        let _ = (input, ctx);
+        Ok(TypedToolHandler::requires_approval(self).into())
+    }
+    async fn preview(
+        &self,
+        input: &Self::Input,
+        ctx: &ToolContext,
+    ) -> Result<Option<Preview>, NeboError> {
+        let _ = (input, ctx);
+        Ok(None)
+    }
+    fn reports_progress(&self) -> bool {
+        false
+    }
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: TypedToolHandler> ToolHandler for T {
+    fn name(&self) -> &str {
+        TypedToolHandler::name(self)
+    }
+
+    fn description(&self) -> &str {
+        TypedToolHandler::description(self)
+    }
+
+    fn schema(&self) -> Value {
+        TypedToolHandler::schema(self)
+    }
+
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let input = parse_input(TypedToolHandler::name(self), input)?;
+        TypedToolHandler::execute(self, input, ctx).await
+    }
+
+    fn requires_approval(&self) -> bool {
+        TypedToolHandler::requires_approval(self)
+    }
+
+    async fn approval(&self, input: &Value, ctx: &ToolContext) -> Result<Approval, NeboError> {
+        let input = parse_input(TypedToolHandler::name(self), input.clone())?;
+        TypedToolHandler::approval(self, &input, ctx).await
+    }
+
+    async fn preview(&self, input: &Value, ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let input = parse_input(TypedToolHandler::name(self), input.clone())?;
+        TypedToolHandler::preview(self, &input, ctx).await
+    }
+
+    fn reports_progress(&self) -> bool {
+        TypedToolHandler::reports_progress(self)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        TypedToolHandler::timeout(self)
+    }
+
+    async fn on_start(&self) -> Result<(), NeboError> {
+        TypedToolHandler::on_start(self).await
+    }
+
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        TypedToolHandler::on_shutdown(self).await
+    }
+}
+
+/// Deserialize tool input into `T`, reporting failures as
+/// [`NeboError::InvalidInput`] with a message the model can act on.
+pub fn parse_input<T: DeserializeOwned>(tool: &str, input: Value) -> Result<T, NeboError> {
+    serde_path_to_error::deserialize(input).map_err(|e| {
+        let path = e.path().to_string();
+        let message = if path == "." {
+            e.into_inner().to_string()
+        } else {
+            format!("at `{}`: {}", path, e.into_inner())
+        };
+        NeboError::InvalidInput {
+            tool: tool.to_string(),
+            message,
+        }
+    })
+}
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<ToolOutput, NeboError>> + Send>>;
+type ToolFn = Box<dyn Fn(Value, ToolContext) -> ToolFuture + Send + Sync>;
+
+/// A tool backed by a closure; see [`tool_fn`] and [`typed_tool_fn`].
+pub struct FnTool {
+    name: String,
+    description: String,
+    schema: Value,
+    handler: ToolFn,
+    requires_approval: bool,
+    reports_progress: bool,
+    timeout: Option<Duration>,
+}
+
+/// Build a tool from a closure that receives the validated input and the
+/// call's [`ToolContext`].
+///
+/// ```no_run
+/// # use nebo_sdk::{tool_fn, NeboApp, SchemaBuilder};
+/// # async fn run() -> Result<(), nebo_sdk::NeboError> {
+/// let echo = tool_fn(
+///     "echo",
+///     "Repeats the given text back.",
+///     SchemaBuilder::new(&[]).string("text", "Text to repeat", true).build(),
+///     |input, _ctx| async move { Ok(input["text"].as_str().unwrap_or_default().to_string()) },
+/// );
+/// NeboApp::new()?.register_tool(echo).run().await
+/// # }
+/// ```
+pub fn tool_fn<F, Fut, O>(name: &str, description: &str, schema: Value, f: F) -> FnTool
+where
+    F: Fn(Value, ToolContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<O, NeboError>> + Send + 'static,
+    O: Into<ToolOutput>,
+{
+    FnTool {
+        name: name.to_string(),
+        description: description.to_string(),
+        schema,
+        handler: Box::new(move |input, ctx| {
+            let fut = f(input, ctx);
+            Box::pin(async move { fut.await.map(Into::into) })
+        }),
+        requires_approval: false,
+        reports_progress: false,
+        timeout: None,
+    }
+}
+
+/// Like [`tool_fn`], with input deserialized into `I` and the schema taken
+/// from `I::nebo_schema()`.
+pub fn typed_tool_fn<I, F, Fut, O>(name: &str, description: &str, f: F) -> FnTool
+where
+    I: DeserializeOwned + NeboSchema + Send + 'static,
+    F: Fn(I, ToolContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<O, NeboError>> + Send + 'static,
+    O: Into<ToolOutput>,
+{
+    let tool = name.to_string();
+    let f = Arc::new(f);
+    tool_fn(name, description, I::nebo_schema(), move |input, ctx| {
+        let parsed = parse_input::<I>(&tool, input);
+        let f = f.clone();
+        async move { f(parsed?, ctx).await }
+    })
+}
+
+impl FnTool {
+    /// Require user approval before every call.
+    pub fn requires_approval(mut self, yes: bool) -> Self {
+        self.requires_approval = yes;
+        self
+    }
+
+    /// Declare that the closure reports through [`ToolContext::progress`].
+    pub fn reports_progress(mut self, yes: bool) -> Self {
+        self.reports_progress = yes;
+        self
+    }
+
+    /// Maximum run time for one call.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl ToolHandler for FnTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, input: Value, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        (self.handler)(input, ctx.clone()).await
+    }
+
+    fn requires_approval(&self) -> bool {
+        self.requires_approval
+    }
+
+    fn reports_progress(&self) -> bool {
+        self.reports_progress
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct ToolBridge {
-    pub handler: Box<dyn ToolHandler>,
-    pub on_configure: Option<Box<dyn Fn(std::collections::HashMap<String, String>) + Send + Sync>>,
+    pub tools: ToolRegistry,
+    pub settings: Settings,
     pub env: AppEnv,
+    pub plans: Arc<PlanStore>,
+    pub default_timeout: Option<Duration>,
+    pub jobs: Jobs,
+}
+
+/// How long a previewed plan waits for approval before it expires.
+const PLAN_TTL: Duration = Duration::from_secs(15 * 60);
+
+struct PendingPlan {
+    tool: String,
+    input: Value,
+    plan: Value,
+    created: Instant,
+}
+
+/// Plans returned by `Preview`, held until the matching `Execute` call.
+#[derive(Default)]
+pub(crate) struct PlanStore {
+    plans: Mutex<HashMap<String, PendingPlan>>,
+    next: AtomicU64,
+}
+
+impl PlanStore {
+    fn insert(&self, tool: &str, input: Value, plan: Value) -> String {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let id = format!("plan-{:x}-{}", stamp, self.next.fetch_add(1, Ordering::Relaxed));
+        let mut plans = self.plans.lock().unwrap();
+        plans.retain(|_, p| p.created.elapsed() < PLAN_TTL);
+        plans.insert(
+            id.clone(),
+            PendingPlan {
+                tool: tool.to_string(),
+                input,
+                plan,
+                created: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// Remove and return a plan, checking it was previewed for this call.
+    fn take(&self, id: &str, tool: &str, input: &Value) -> Result<Value, NeboError> {
+        let pending = self
+            .plans
+            .lock()
+            .unwrap()
+            .remove(id)
+            .filter(|p| p.created.elapsed() < PLAN_TTL)
+            .ok_or_else(|| {
+                NeboError::Execution(format!("plan {id} is unknown or expired; preview the call again"))
+            })?;
+        if pending.tool != tool || &pending.input != input {
+            return Err(NeboError::Execution(format!(
+                "plan {id} was previewed for different input; preview the call again"
+            )));
+        }
+        Ok(pending.plan)
+    }
+}
+
+/// Parse the JSON input of an execute call and check it against the tool's
+/// schema. An empty payload is treated as an empty object.
+fn decode_input(handler: &dyn ToolHandler, raw: &[u8]) -> Result<Value, NeboError> {
+    let input = if raw.is_empty() {
+        Value::Object(serde_json::Map::new())
+    } else {
+        serde_json::from_slice(raw).map_err(|e| NeboError::InvalidInput {
+            tool: handler.name().to_string(),
+            message: format!("input is not valid JSON ({e})"),
+        })?
+    };
+    check_input(handler.name(), &handler.schema(), &input)?;
+    Ok(input)
+}
+
+impl ToolBridge {
+    /// Resolve, validate and run one execute call, stopping it at the tool's
+    /// timeout or `ctx.deadline`, whichever comes first. Failures are
+    /// reported in the response so the model sees them. `ctx.cancel` fires
+    /// when this future finishes or is dropped, whichever comes first.
+    pub(crate) async fn run(&self, req: pb::ExecuteRequest, mut ctx: ToolContext) -> pb::ExecuteResponse {
+        let _cancel_on_drop = ctx.cancel.clone().drop_guard();
+        let Some(handler) = self.tools.resolve(&req.tool_name) else {
+            return error_response(format!("unknown tool: {}", req.tool_name));
+        };
+        ctx.tool_name = handler.name().to_string();
+        let input = match decode_input(handler.as_ref(), &req.input) {
+            Ok(input) => input,
+            Err(e) => return failure(e),
+        };
+        if !req.plan_id.is_empty() {
+            match self.plans.take(&req.plan_id, handler.name(), &input) {
+                Ok(plan) => ctx.approved_plan = Some(plan),
+                Err(e) => return failure(e),
+            }
+        }
+        // The caller's deadline bounds the call as well. Stop just short of
+        // it so the timeout response still reaches the caller.
+        let remaining = ctx
+            .deadline
+            .map(|d| d.saturating_duration_since(Instant::now()).saturating_sub(DEADLINE_MARGIN));
+        let limit = match (handler.timeout().or(self.default_timeout), remaining) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let result = match limit {
+            Some(after) => tokio::time::timeout(after, handler.execute(input, &ctx))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(NeboError::Timeout {
+                        tool: handler.name().to_string(),
+                        after,
+                    })
+                }),
+            None => handler.execute(input, &ctx).await,
+        };
+        match result {
+            Ok(output) => output.into_response(),
+            Err(e) => failure(e),
+        }
+    }
+
+    fn context(&self, req: &mut pb::ExecuteRequest, metadata: &MetadataMap) -> ToolContext {
+        let mut ctx = ToolContext::new(self.env.clone(), req.user.take()).with_jobs(self.jobs.clone());
+        ctx.deadline = grpc_timeout(metadata).map(|t| Instant::now() + t);
+        ctx
+    }
+}
+
+/// How long before the caller's deadline a call is stopped.
+const DEADLINE_MARGIN: Duration = Duration::from_millis(20);
+
+/// Parse the `grpc-timeout` header (e.g. `30S`, `500m`) set by callers with a
+/// deadline.
+fn grpc_timeout(metadata: &MetadataMap) -> Option<Duration> {
+    let value = metadata.get("grpc-timeout")?.to_str().ok()?;
+    let (amount, unit) = value.split_at(value.len().checked_sub(1)?);
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 3600),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+fn error_response(e: impl ToString) -> pb::ExecuteResponse {
+    pb::ExecuteResponse {
+        content: e.to_string(),
+        is_error: true,
+        ..Default::default()
+    }
+}
+
+/// An error response tagged with a machine-readable code where one applies.
+fn failure(e: NeboError) -> pb::ExecuteResponse {
+    let error_code = match e {
+        NeboError::Timeout { .. } => "timeout",
+        _ => "",
+    };
+    pb::ExecuteResponse {
+        error_code: error_code.to_string(),
+        ..error_response(e)
+    }
+}
+
+fn preview_error(e: NeboError) -> pb::PreviewResponse {
+    pb::PreviewResponse {
+        supported: true,
+        preview: Some(error_response(e)),
+        plan_id: String::new(),
+    }
+}
+
+fn unknown_job(id: &str) -> Status {
+    Status::not_found(format!("unknown job: {id}"))
+}
+
+fn no_tools() -> Status {
+    Status::failed_precondition("no tools registered")
+}
+
+fn unknown_tool(name: &str) -> Status {
+    Status::not_found(format!("unknown tool: {name}"))
 }
 
 #[tonic::async_trait]
@@ -41,8 +515,9 @@ impl pb::tool_service_server::ToolService for ToolBridge {
         &self,
         _req: Request<pb::Empty>,
     ) -> Result<Response<pb::NameResponse>, Status> {
+        let tool = self.tools.default_tool().ok_or_else(no_tools)?;
         Ok(Response::new(pb::NameResponse {
-            name: self.handler.name().to_string(),
+            name: tool.name().to_string(),
         }))
     }
 
@@ -50,8 +525,9 @@ impl pb::tool_service_server::ToolService for ToolBridge {
         &self,
         _req: Request<pb::Empty>,
     ) -> Result<Response<pb::DescriptionResponse>, Status> {
+        let tool = self.tools.default_tool().ok_or_else(no_tools)?;
         Ok(Response::new(pb::DescriptionResponse {
-            description: self.handler.description().to_string(),
+            description: tool.description().to_string(),
         }))
     }
 
@@ -59,7 +535,8 @@ impl pb::tool_service_server::ToolService for ToolBridge {
         &self,
         _req: Request<pb::Empty>,
     ) -> Result<Response<pb::SchemaResponse>, Status> {
-        let schema = serde_json::to_vec(&self.handler.schema()).unwrap_or_default();
+        let tool = self.tools.default_tool().ok_or_else(no_tools)?;
+        let schema = serde_json::to_vec(&tool.schema()).unwrap_or_default();
         Ok(Response::new(pb::SchemaResponse { schema }))
     }
 
@@ -67,27 +544,49 @@ impl pb::tool_service_server::ToolService for ToolBridge {
         &self,
         req: Request<pb::ExecuteRequest>,
     ) -> Result<Response<pb::ExecuteResponse>, Status> {
-        let input: Value = serde_json::from_slice(&req.into_inner().input)
-            .unwrap_or(Value::Object(serde_json::Map::new()));
-
-        match self.handler.execute(input).await {
-            Ok(content) => Ok(Response::new(pb::ExecuteResponse {
-                content,
-                is_error: false,
-            })),
-            Err(e) => Ok(Response::new(pb::ExecuteResponse {
-                content: e.to_string(),
-                is_error: true,
-            })),
-        }
+        let metadata = req.metadata().clone();
+        let mut inner = req.into_inner();
+        let ctx = self.context(&mut inner, &metadata);
+        Ok(Response::new(self.run(inner, ctx).await))
+    }
+
+    type ExecuteStreamStream =
+        tokio_stream::wrappers::UnboundedReceiverStream<Result<pb::ExecuteEvent, Status>>;
+
+    async fn execute_stream(
+        &self,
+        req: Request<pb::ExecuteRequest>,
+    ) -> Result<Response<Self::ExecuteStreamStream>, Status> {
+        let metadata = req.metadata().clone();
+        let mut inner = req.into_inner();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut ctx = self.context(&mut inner, &metadata);
+        ctx.progress = ProgressSink::new(tx.clone());
+
+        let bridge = self.clone();
+        tokio::spawn(async move {
+            // Dropping the run when the client goes away cancels the call.
+            let result = tokio::select! {
+                result = bridge.run(inner, ctx) => result,
+                _ = tx.closed() => return,
+            };
+            let _ = tx.send(Ok(pb::ExecuteEvent {
+                event: Some(pb::execute_event::Event::Result(result)),
+            }));
+        });
+
+        Ok(Response::new(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+        ))
     }
 
     async fn requires_approval(
         &self,
         _req: Request<pb::Empty>,
     ) -> Result<Response<pb::ApprovalResponse>, Status> {
+        let tool = self.tools.default_tool().ok_or_else(no_tools)?;
         Ok(Response::new(pb::ApprovalResponse {
-            requires_approval: self.handler.requires_approval(),
+            requires_approval: tool.requires_approval(),
         }))
     }
 
@@ -95,9 +594,160 @@ impl pb::tool_service_server::ToolService for ToolBridge {
         &self,
         req: Request<pb::SettingsMap>,
     ) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
+
+    async fn list_tools(
+        &self,
+        _req: Request<pb::Empty>,
+    ) -> Result<Response<pb::ListToolsResponse>, Status> {
+        let tools = self
+            .tools
+            .iter()
+            .map(|t| pb::ToolDefinition {
+                name: t.name().to_string(),
+                description: t.description().to_string(),
+                schema: serde_json::to_vec(&t.schema()).unwrap_or_default(),
+                requires_approval: t.requires_approval(),
+                streaming: t.reports_progress(),
+            })
+            .collect();
+        Ok(Response::new(pb::ListToolsResponse { tools }))
+    }
+
+    async fn tool_schema(
+        &self,
+        req: Request<pb::ToolNameRequest>,
+    ) -> Result<Response<pb::SchemaResponse>, Status> {
+        let name = req.into_inner().name;
+        let tool = self.tools.resolve(&name).ok_or_else(|| unknown_tool(&name))?;
+        let schema = serde_json::to_vec(&tool.schema()).unwrap_or_default();
+        Ok(Response::new(pb::SchemaResponse { schema }))
+    }
+
+    async fn tool_requires_approval(
+        &self,
+        req: Request<pb::ToolNameRequest>,
+    ) -> Result<Response<pb::ApprovalResponse>, Status> {
+        let name = req.into_inner().name;
+        let tool = self.tools.resolve(&name).ok_or_else(|| unknown_tool(&name))?;
+        Ok(Response::new(pb::ApprovalResponse {
+            requires_approval: tool.requires_approval(),
+        }))
+    }
+
+    async fn check_approval(
+        &self,
+        req: Request<pb::ExecuteRequest>,
+    ) -> Result<Response<pb::ApprovalDecision>, Status> {
+        let mut inner = req.into_inner();
+        let tool = self
+            .tools
+            .resolve(&inner.tool_name)
+            .ok_or_else(|| unknown_tool(&inner.tool_name))?;
+        // Input that fails validation is rejected by Execute before the tool
+        // runs, so there is nothing to approve.
+        let Ok(input) = decode_input(tool.as_ref(), &inner.input) else {
+            return Ok(Response::new(Approval::not_required().into()));
+        };
+        let ctx = ToolContext::new(self.env.clone(), inner.user.take());
+        let approval = match tool.approval(&input, &ctx).await {
+            Ok(approval) => approval,
+            // Likewise for typed input that fails to parse.
+            Err(NeboError::InvalidInput { .. }) => Approval::not_required(),
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+        Ok(Response::new(approval.into()))
+    }
+
+    async fn preview(
+        &self,
+        req: Request<pb::ExecuteRequest>,
+    ) -> Result<Response<pb::PreviewResponse>, Status> {
+        let mut inner = req.into_inner();
+        let tool = self
+            .tools
+            .resolve(&inner.tool_name)
+            .ok_or_else(|| unknown_tool(&inner.tool_name))?;
+        let input = match decode_input(tool.as_ref(), &inner.input) {
+            Ok(input) => input,
+            Err(e) => return Ok(Response::new(preview_error(e))),
+        };
+        let ctx = ToolContext::new(self.env.clone(), inner.user.take());
+        let preview = match tool.preview(&input, &ctx).await {
+            Ok(Some(preview)) => preview,
+            Ok(None) => return Ok(Response::new(pb::PreviewResponse::default())),
+            Err(e) => return Ok(Response::new(preview_error(e))),
+        };
+        let plan_id = self.plans.insert(tool.name(), input, preview.plan);
+        Ok(Response::new(pb::PreviewResponse {
+            supported: true,
+            preview: Some(preview.output.into_response()),
+            plan_id,
+        }))
+    }
+
+    async fn job_status(
+        &self,
+        req: Request<pb::JobRequest>,
+    ) -> Result<Response<pb::JobInfo>, Status> {
+        let id = req.into_inner().job_id;
+        let info = self.jobs.status(&id).ok_or_else(|| unknown_job(&id))?;
+        Ok(Response::new(info))
+    }
+
+    async fn job_result(
+        &self,
+        req: Request<pb::JobRequest>,
+    ) -> Result<Response<pb::ExecuteResponse>, Status> {
+        let id = req.into_inner().job_id;
+        let result = self
+            .jobs
+            .result(&id)
+            .ok_or_else(|| unknown_job(&id))?
+            .ok_or_else(|| Status::failed_precondition(format!("job {id} is still running")))?;
+        Ok(Response::new(result))
+    }
+
+    async fn cancel_job(
+        &self,
+        req: Request<pb::JobRequest>,
+    ) -> Result<Response<pb::JobInfo>, Status> {
+        let id = req.into_inner().job_id;
+        let info = self.jobs.cancel(&id).ok_or_else(|| unknown_job(&id))?;
+        Ok(Response::new(info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn plans_are_taken_once_for_the_same_call() {
+        let plans = PlanStore::default();
+        let input = json!({"path": "a"});
+        let id = plans.insert("delete", input.clone(), json!(["a"]));
+        assert!(plans.take(&id, "other", &input).is_err());
+
+        let id = plans.insert("delete", input.clone(), json!(["a"]));
+        assert_eq!(plans.take(&id, "delete", &input).unwrap(), json!(["a"]));
+        assert!(plans.take(&id, "delete", &input).is_err());
+    }
+
+    #[test]
+    fn expired_plans_are_rejected() {
+        let plans = PlanStore::default();
+        let input = json!({"path": "a"});
+        let id = plans.insert("delete", input.clone(), json!(["a"]));
+        let expired = Instant::now().checked_sub(PLAN_TTL).unwrap();
+        plans.plans.lock().unwrap().get_mut(&id).unwrap().created = expired;
+
+        let err = plans.take(&id, "delete", &input).unwrap_err();
+        assert!(err.to_string().contains("unknown or expired"), "{err}");
+    }
 }
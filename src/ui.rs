@@ -1,10 +1,14 @@
 use async_trait::async_trait;
+use std::sync::Arc;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use tonic::{Request, Response, Status};
 
 use crate::env::AppEnv;
 use crate::error::NeboError;
 use crate::pb;
+use crate::settings::Settings;
 
 /// An HTTP request proxied from the browser to the app.
 #[derive(Debug, Clone)]
@@ -28,11 +32,60 @@ pub struct HttpResponse {
 #[async_trait]
 pub trait UiHandler: Send + Sync + 'static {
     async fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, NeboError>;
+    /// Called once before the app starts serving.
+    async fn on_start(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+    /// Called once after in-flight requests have drained on shutdown.
+    async fn on_shutdown(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+type UiFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, NeboError>> + Send>>;
+
+/// A UI handler backed by a closure; see [`ui_fn`].
+pub struct FnUi {
+    handler: Box<dyn Fn(HttpRequest) -> UiFuture + Send + Sync>,
+}
+
+/// Build a UI handler from a closure that answers each request.
+///
+/// ```no_run
+/// # use std::collections::HashMap;
+/// # use nebo_sdk::ui::HttpResponse;
+/// # use nebo_sdk::{ui_fn, NeboApp};
+/// # async fn run() -> Result<(), nebo_sdk::NeboError> {
+/// let ui = ui_fn(|req| async move {
+///     Ok(HttpResponse {
+///         status_code: 200,
+///         headers: HashMap::from([("content-type".into(), "text/plain".into())]),
+///         body: format!("you asked for {}", req.path).into_bytes(),
+///     })
+/// });
+/// NeboApp::new()?.register_ui(ui).run().await
+/// # }
+/// ```
+pub fn ui_fn<F, Fut>(f: F) -> FnUi
+where
+    F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<HttpResponse, NeboError>> + Send + 'static,
+{
+    FnUi {
+        handler: Box::new(move |req| Box::pin(f(req))),
+    }
+}
+
+#[async_trait]
+impl UiHandler for FnUi {
+    async fn handle_request(&self, req: HttpRequest) -> Result<HttpResponse, NeboError> {
+        (self.handler)(req).await
+    }
 }
 
 pub(crate) struct UiBridge {
-    pub handler: Box<dyn UiHandler>,
-    pub on_configure: Option<Box<dyn Fn(HashMap<String, String>) + Send + Sync>>,
+    pub handler: Arc<dyn UiHandler>,
+    pub settings: Settings,
     pub env: AppEnv,
 }
 
@@ -79,9 +132,9 @@ impl pb::ui_service_server::UiService for UiBridge {
         &self,
         req: Request<pb::SettingsMap>,
     ) -> Result<Response<pb::Empty>, Status> {
-        if let Some(ref cb) = self.on_configure {
-            cb(req.into_inner().values);
-        }
+        self.settings
+            .apply(req.into_inner().values)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
         Ok(Response::new(pb::Empty {}))
     }
 }
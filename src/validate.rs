@@ -0,0 +1,360 @@
+use serde_json::Value;
+use std::fmt;
+
+use crate::error::NeboError;
+use crate::schema::{describe_condition, join_path};
+
+/// One way in which tool input fails to match its JSON Schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Location of the offending value, e.g. `items[2].name`; empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "input {}", self.message)
+        } else {
+            write!(f, "`{}` {}", self.path, self.message)
+        }
+    }
+}
+
+/// Validate `input` against a JSON Schema, returning every violation found.
+///
+/// Supports the subset of JSON Schema produced by [`SchemaBuilder`](crate::SchemaBuilder)
+/// and `#[derive(NeboSchema)]`: `type`, `required`, `enum`, `const`,
+/// `properties`, `additionalProperties`, `items`, numeric bounds, string
+/// length and `pattern`, array length and `uniqueItems`, and `allOf` with
+/// `if`/`then`/`else`. Other keywords (including `format`) are ignored.
+/// Optional properties may be `null`.
+pub fn validate(schema: &Value, input: &Value) -> Vec<SchemaViolation> {
+    let mut out = Vec::new();
+    check(schema, input, "", &mut out);
+    out
+}
+
+/// Validate tool input, reporting all violations as one
+/// [`NeboError::InvalidInput`] so the model can fix every problem at once.
+pub fn check_input(tool: &str, schema: &Value, input: &Value) -> Result<(), NeboError> {
+    let violations = validate(schema, input);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let message = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    Err(NeboError::InvalidInput {
+        tool: tool.to_string(),
+        message,
+    })
+}
+
+fn check(schema: &Value, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let mut fail = |message: String| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+            fail(format!("must be {}, got {}", types.join(" or "), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let list: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            fail(format!("must be one of {}, got {}", list.join(", "), value));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            fail(format!("must be {}, got {}", expected, value));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        let bound = |key| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum").filter(|min| n < *min) {
+            fail(format!("must be at least {min}, got {n}"));
+        }
+        if let Some(max) = bound("maximum").filter(|max| n > *max) {
+            fail(format!("must be at most {max}, got {n}"));
+        }
+        if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+            fail(format!("must be greater than {min}, got {n}"));
+        }
+        if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+            fail(format!("must be less than {max}, got {n}"));
+        }
+    }
+
+    if let Some(s) = value.as_str() {
+        let len = s.chars().count() as u64;
+        if let Some(min) = limit(schema, "minLength").filter(|min| len < *min) {
+            fail(format!("must be at least {min} characters long"));
+        }
+        if let Some(max) = limit(schema, "maxLength").filter(|max| len > *max) {
+            fail(format!("must be at most {max} characters long"));
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            // Patterns the regex crate cannot compile are ignored.
+            if regex::Regex::new(pattern).is_ok_and(|re| !re.is_match(s)) {
+                fail(format!("must match pattern {pattern}"));
+            }
+        }
+    }
+
+    if let Some(arr) = value.as_array() {
+        let len = arr.len() as u64;
+        if let Some(min) = limit(schema, "minItems").filter(|min| len < *min) {
+            fail(format!("must have at least {min} items"));
+        }
+        if let Some(max) = limit(schema, "maxItems").filter(|max| len > *max) {
+            fail(format!("must have at most {max} items"));
+        }
+        let unique = schema.get("uniqueItems").and_then(Value::as_bool) == Some(true);
+        if unique && arr.iter().enumerate().any(|(i, v)| arr[..i].contains(v)) {
+            fail("must not contain duplicate items".to_string());
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        for &name in &required {
+            if !obj.contains_key(name) {
+                out.push(SchemaViolation {
+                    path: join_path(path, name),
+                    message: "is required".to_string(),
+                });
+            }
+        }
+
+        let props = schema.get("properties").and_then(Value::as_object);
+        for (key, v) in obj {
+            // Optional properties may be sent as null, matching serde's Option handling.
+            if v.is_null() && !required.contains(&key.as_str()) {
+                continue;
+            }
+            match props.and_then(|p| p.get(key)) {
+                Some(sub) => check(sub, v, &join_path(path, key), out),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => out.push(SchemaViolation {
+                        path: join_path(path, key),
+                        message: "is not an allowed property".to_string(),
+                    }),
+                    Some(sub @ Value::Object(_)) => check(sub, v, &join_path(path, key), out),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (i, v) in arr.iter().enumerate() {
+            check(items, v, &format!("{path}[{i}]"), out);
+        }
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            check(sub, value, path, out);
+        }
+    }
+
+    if let Some(cond) = schema.get("if") {
+        if validate(cond, value).is_empty() {
+            if let Some(then) = schema.get("then") {
+                let mut found = Vec::new();
+                check(then, value, path, &mut found);
+                let when = describe_condition(cond);
+                out.extend(found.into_iter().map(|mut v| {
+                    if let Some(when) = &when {
+                        v.message = format!("{} when {}", v.message, when);
+                    }
+                    v
+                }));
+            }
+        } else if let Some(otherwise) = schema.get("else") {
+            check(otherwise, value, path, out);
+        }
+    }
+}
+
+fn limit(schema: &serde_json::Map<String, Value>, key: &str) -> Option<u64> {
+    schema.get(key).and_then(Value::as_u64)
+}
+
+fn is_type(value: &Value, t: &str) -> bool {
+    match t {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        // Match serde: `1.0` does not deserialize into an integer type.
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Prop, SchemaBuilder};
+    use serde_json::json;
+
+    fn messages(schema: &Value, input: Value) -> Vec<String> {
+        validate(schema, &input).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let schema = SchemaBuilder::new(&["add", "sub"])
+            .number("a", "First operand", true)
+            .string("note", "Comment", false)
+            .build();
+        assert!(validate(&schema, &json!({"action": "add", "a": 1})).is_empty());
+        assert_eq!(
+            messages(&schema, json!({"action": "mul", "note": 3})),
+            [
+                "`a` is required",
+                "`action` must be one of \"add\", \"sub\", got \"mul\"",
+                "`note` must be string, got number",
+            ]
+        );
+        assert_eq!(messages(&schema, json!([])), ["input must be object, got array"]);
+    }
+
+    #[test]
+    fn integers_must_not_have_a_fraction() {
+        let schema = json!({"type": "integer"});
+        assert!(validate(&schema, &json!(3)).is_empty());
+        assert!(validate(&schema, &json!(u64::MAX)).is_empty());
+        assert_eq!(messages(&schema, json!(1.0)), ["input must be integer, got number"]);
+        assert_eq!(messages(&schema, json!(1.5)), ["input must be integer, got number"]);
+    }
+
+    #[test]
+    fn check_input_joins_violations() {
+        let schema = SchemaBuilder::new(&["add"]).number("a", "First operand", true).build();
+        match check_input("calc", &schema, &json!({})) {
+            Err(NeboError::InvalidInput { tool, message }) => {
+                assert_eq!(tool, "calc");
+                assert_eq!(message, "`action` is required; `a` is required");
+            }
+            other => panic!("expected invalid input, got {other:?}"),
+        }
+        assert!(check_input("calc", &schema, &json!({"action": "add", "a": 2})).is_ok());
+    }
+
+    #[test]
+    fn optional_properties_may_be_null() {
+        let schema = SchemaBuilder::new(&[])
+            .string("name", "Name", true)
+            .string("note", "Comment", false)
+            .build();
+        assert!(validate(&schema, &json!({"name": "x", "note": null})).is_empty());
+        assert_eq!(
+            messages(&schema, json!({"name": null})),
+            ["`name` must be string, got null"]
+        );
+    }
+
+    #[test]
+    fn checks_rich_types() {
+        let item = SchemaBuilder::new(&[]).string("id", "Item ID", true);
+        let schema = SchemaBuilder::new(&[])
+            .property("count", Prop::integer().minimum(1).maximum(10), true)
+            .property("code", Prop::string().min_length(2).max_length(3).pattern("^[A-Z]+$"), false)
+            .property("tags", Prop::array(Prop::string()).max_items(2).unique_items(), false)
+            .array("items", "Items", false, Prop::object(item))
+            .build();
+        assert!(validate(
+            &schema,
+            &json!({"count": 3, "code": "AB", "tags": ["a"], "items": [{"id": "x"}]})
+        )
+        .is_empty());
+        assert_eq!(
+            messages(
+                &schema,
+                json!({
+                    "count": 1.5,
+                    "code": "abcd",
+                    "tags": ["a", "a", "b"],
+                    "items": [{"id": 1}, {}],
+                })
+            ),
+            [
+                "`code` must be at most 3 characters long",
+                "`code` must match pattern ^[A-Z]+$",
+                "`count` must be integer, got number",
+                "`items[0].id` must be string, got number",
+                "`items[1].id` is required",
+                "`tags` must have at most 2 items",
+                "`tags` must not contain duplicate items",
+            ]
+        );
+        assert_eq!(messages(&schema, json!({"count": 0})), ["`count` must be at least 1, got 0"]);
+    }
+
+    #[test]
+    fn action_requirements_apply_only_to_their_action() {
+        let schema = SchemaBuilder::new(&["add", "divide"])
+            .number("a", "Dividend", true)
+            .number("b", "Divisor", false)
+            .action_requires("divide", &["b"])
+            .build();
+        assert!(validate(&schema, &json!({"action": "add", "a": 1})).is_empty());
+        assert!(validate(&schema, &json!({"action": "divide", "a": 1, "b": 2})).is_empty());
+        assert_eq!(
+            messages(&schema, json!({"action": "divide", "a": 1})),
+            ["`b` is required when `action` is \"divide\""]
+        );
+    }
+
+    #[test]
+    fn else_branch_applies_when_condition_fails() {
+        let schema = json!({
+            "type": "object",
+            "if": {"properties": {"kind": {"const": "a"}}},
+            "then": {"required": ["a"]},
+            "else": {"required": ["b"]},
+        });
+        assert!(validate(&schema, &json!({"kind": "a", "a": 1})).is_empty());
+        assert_eq!(messages(&schema, json!({"kind": "z"})), ["`b` is required"]);
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::tool::TypedToolHandler;
+use nebo_sdk::{Approval, AppEnv, NeboApp, NeboError, NeboSchema, Preview, ToolContext, ToolOutput};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, NeboSchema)]
+struct Target {
+    /// File to delete.
+    path: String,
+}
+
+/// Deletes files, asking first for anything outside `tmp/`.
+struct Delete;
+
+#[async_trait]
+impl TypedToolHandler for Delete {
+    type Input = Target;
+
+    fn name(&self) -> &str {
+        "delete"
+    }
+
+    fn description(&self) -> &str {
+        "Deletes a file."
+    }
+
+    /// Looser than `Target`, so typed parsing is what catches bad input.
+    fn schema(&self) -> Value {
+        json!({"type": "object"})
+    }
+
+    async fn execute(&self, input: Target, ctx: &ToolContext) -> Result<ToolOutput, NeboError> {
+        let plan = ctx.approved_plan.clone().unwrap_or(Value::Null);
+        Ok(ToolOutput::text(format!("deleted {} (plan {})", input.path, plan)))
+    }
+
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
+    async fn approval(&self, input: &Target, _ctx: &ToolContext) -> Result<Approval, NeboError> {
+        if input.path.starts_with("tmp/") {
+            return Ok(Approval::not_required());
+        }
+        Ok(Approval::required(format!("Delete {}", input.path)).with_resource(&input.path))
+    }
+
+    async fn preview(&self, input: &Target, _ctx: &ToolContext) -> Result<Option<Preview>, NeboError> {
+        let preview = Preview::new(format!("would delete {}", input.path));
+        Ok(Some(preview.with_plan(json!({"files": [input.path]}))))
+    }
+}
+
+fn request(input: Value, plan_id: &str) -> pb::ExecuteRequest {
+    pb::ExecuteRequest {
+        input: serde_json::to_vec(&input).unwrap(),
+        plan_id: plan_id.to_string(),
+        ..Default::default()
+    }
+}
+
+async fn start() -> TestApp {
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(Delete))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn check_approval_depends_on_input() {
+    let app = start().await;
+    let decision = app
+        .tool()
+        .check_approval(request(json!({"path": "docs/a.txt"}), ""))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(decision.requires_approval);
+    assert_eq!(decision.prompt, "Delete docs/a.txt");
+    assert_eq!(decision.resources, ["docs/a.txt"]);
+
+    let decision = app
+        .tool()
+        .check_approval(request(json!({"path": "tmp/a.txt"}), ""))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!decision.requires_approval);
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn invalid_input_needs_no_approval_and_fails_on_execute() {
+    let app = start().await;
+    for input in [json!([]), json!({"path": 3})] {
+        let decision = app
+            .tool()
+            .check_approval(request(input.clone(), ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!decision.requires_approval, "{input}");
+
+        let resp = app.tool().execute(request(input.clone(), "")).await.unwrap().into_inner();
+        assert!(resp.is_error, "{input}");
+    }
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn previewed_plan_is_used_once() {
+    let app = start().await;
+    let input = json!({"path": "docs/a.txt"});
+    let preview = app.tool().preview(request(input.clone(), "")).await.unwrap().into_inner();
+    assert!(preview.supported);
+    assert_eq!(preview.preview.unwrap().content, "would delete docs/a.txt");
+    assert!(!preview.plan_id.is_empty());
+
+    let resp = app
+        .tool()
+        .execute(request(input.clone(), &preview.plan_id))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(resp.content, r#"deleted docs/a.txt (plan {"files":["docs/a.txt"]})"#);
+
+    let resp = app
+        .tool()
+        .execute(request(input, &preview.plan_id))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(resp.is_error);
+    assert!(resp.content.contains("unknown or expired"), "{}", resp.content);
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn plan_for_different_input_is_rejected() {
+    let app = start().await;
+    let preview = app
+        .tool()
+        .preview(request(json!({"path": "docs/a.txt"}), ""))
+        .await
+        .unwrap()
+        .into_inner();
+    let resp = app
+        .tool()
+        .execute(request(json!({"path": "docs/b.txt"}), &preview.plan_id))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(resp.is_error);
+    assert!(resp.content.contains("previewed for different input"), "{}", resp.content);
+    app.shutdown().await.unwrap();
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use nebo_sdk::channel::{ChannelEnvelope, ChannelHandler};
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{AppEnv, NeboApp, NeboError};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Delivers one message, then keeps the receive stream open.
+#[derive(Default)]
+struct Inbox {
+    senders: Mutex<Vec<mpsc::Sender<ChannelEnvelope>>>,
+}
+
+#[async_trait]
+impl ChannelHandler for Inbox {
+    fn id(&self) -> &str {
+        "inbox"
+    }
+
+    async fn connect(&self, _config: HashMap<String, String>) -> Result<(), NeboError> {
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<(), NeboError> {
+        Ok(())
+    }
+
+    async fn send(&self, env: ChannelEnvelope) -> Result<String, NeboError> {
+        Ok(format!("sent-{}", env.text))
+    }
+
+    async fn receive(&self) -> Result<mpsc::Receiver<ChannelEnvelope>, NeboError> {
+        let (tx, rx) = mpsc::channel(1);
+        tx.send(ChannelEnvelope {
+            channel_id: "c1".into(),
+            text: "hello".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        self.senders.lock().unwrap().push(tx);
+        Ok(rx)
+    }
+}
+
+async fn start() -> TestApp {
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_channel(Inbox::default()))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn receive_stream_ends_on_shutdown() {
+    let app = start().await;
+    let mut stream = app.channel().receive(pb::Empty {}).await.unwrap().into_inner();
+    let first = stream.message().await.unwrap().unwrap();
+    assert_eq!((first.channel_id.as_str(), first.text.as_str()), ("c1", "hello"));
+
+    let sock = app.sock_path().to_path_buf();
+    tokio::time::timeout(Duration::from_secs(5), app.shutdown())
+        .await
+        .expect("shutdown waited on an open receive stream")
+        .unwrap();
+    let end = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("receive stream stayed open after shutdown");
+    assert!(matches!(end, Ok(None) | Err(_)), "{end:?}");
+    assert!(!sock.exists());
+}
+
+#[tokio::test]
+async fn send_returns_platform_message_id() {
+    let app = start().await;
+    let mut client = app.channel();
+    assert_eq!(client.id(pb::Empty {}).await.unwrap().into_inner().id, "inbox");
+    let resp = client
+        .send(pb::ChannelSendRequest {
+            channel_id: "c1".into(),
+            text: "hi".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(resp.message_id, "sent-hi");
+    app.shutdown().await.unwrap();
+}
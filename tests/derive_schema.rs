@@ -0,0 +1,154 @@
+use nebo_sdk::schema::NeboSchema;
+use nebo_sdk::validate::validate;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Assert that serde and the schema agree on `input`.
+fn accepts<T: NeboSchema + for<'de> Deserialize<'de>>(input: Value) {
+    let violations = validate(&T::nebo_schema(), &input);
+    assert!(violations.is_empty(), "{input} rejected: {violations:?}");
+    serde_json::from_value::<T>(input).unwrap();
+}
+
+#[test]
+fn struct_fields_docs_and_options() {
+    /// A search query.
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    struct Search {
+        /// Terms to look for.
+        query: String,
+        limit: Option<u32>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+    assert_eq!(
+        Search::nebo_schema(),
+        json!({
+            "type": "object",
+            "description": "A search query.",
+            "properties": {
+                "query": {"type": "string", "description": "Terms to look for."},
+                "limit": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+            },
+            "required": ["query"],
+        })
+    );
+    accepts::<Search>(json!({"query": "rust"}));
+}
+
+#[test]
+fn container_default_makes_every_field_optional() {
+    #[allow(dead_code)]
+    #[derive(Default, Deserialize, NeboSchema)]
+    #[serde(default)]
+    struct Options {
+        a: String,
+        b: u32,
+    }
+    assert_eq!(Options::nebo_schema()["required"], json!([]));
+    accepts::<Options>(json!({}));
+    accepts::<Options>(json!({"b": 2}));
+}
+
+#[test]
+fn rename_rename_all_and_skip() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    #[serde(rename_all = "camelCase")]
+    struct Event {
+        start_time: String,
+        #[serde(rename = "kind")]
+        event_type: String,
+        #[serde(skip)]
+        cache: Option<String>,
+        r#type: String,
+    }
+    let schema = Event::nebo_schema();
+    let mut names: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+    names.sort();
+    assert_eq!(names, ["kind", "startTime", "type"]);
+    assert_eq!(schema["required"], json!(["startTime", "kind", "type"]));
+    accepts::<Event>(json!({"startTime": "now", "kind": "call", "type": "x"}));
+}
+
+#[test]
+fn unit_enum_with_rename_all() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Level {
+        Low,
+        VeryHigh,
+    }
+    assert_eq!(Level::nebo_schema(), json!({"type": "string", "enum": ["LOW", "VERY_HIGH"]}));
+    accepts::<Level>(json!("VERY_HIGH"));
+}
+
+#[test]
+fn flatten_merges_fields() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    struct Page {
+        offset: u32,
+        limit: Option<u32>,
+    }
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    struct List {
+        filter: String,
+        #[serde(flatten)]
+        page: Page,
+    }
+    let schema = List::nebo_schema();
+    assert_eq!(schema["required"], json!(["filter", "offset"]));
+    assert_eq!(schema["properties"]["limit"], json!({"type": "integer"}));
+    accepts::<List>(json!({"filter": "open", "offset": 10}));
+}
+
+#[test]
+fn tagged_enum_is_strap_schema() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    struct Operands {
+        a: f64,
+        b: f64,
+    }
+    /// Calculator input.
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    #[serde(tag = "action", rename_all = "snake_case")]
+    enum Calc {
+        Add(Operands),
+        Negate { a: f64 },
+        Reset,
+        #[serde(rename = "sqrt")]
+        SquareRoot { a: f64, precision: Option<u8> },
+    }
+    let schema = Calc::nebo_schema();
+    assert_eq!(schema["description"], "Calculator input.");
+    assert_eq!(schema["properties"]["action"]["enum"], json!(["add", "negate", "reset", "sqrt"]));
+    assert_eq!(schema["required"], json!(["action"]));
+    accepts::<Calc>(json!({"action": "add", "a": 1, "b": 2}));
+    accepts::<Calc>(json!({"action": "reset"}));
+    accepts::<Calc>(json!({"action": "sqrt", "a": 4}));
+
+    // Newtype variants require the wrapped struct's fields for that action.
+    let errors = validate(&schema, &json!({"action": "add", "a": 1}));
+    assert_eq!(errors[0].to_string(), "`b` is required when `action` is \"add\"");
+    assert!(validate(&schema, &json!({"action": "divide"})).len() == 1);
+}
+
+#[test]
+fn generic_struct() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, NeboSchema)]
+    struct Wrapper<T> {
+        items: Vec<T>,
+    }
+    assert_eq!(
+        Wrapper::<bool>::nebo_schema()["properties"]["items"],
+        json!({"type": "array", "items": {"type": "boolean"}})
+    );
+}
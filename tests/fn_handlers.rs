@@ -0,0 +1,96 @@
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::ui::{HttpRequest, HttpResponse};
+use nebo_sdk::{tool_fn, typed_tool_fn, ui_fn, AppEnv, NeboApp, NeboSchema, SchemaBuilder};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, NeboSchema)]
+struct Print {
+    /// Document to print.
+    name: String,
+    /// Number of copies.
+    copies: u8,
+}
+
+async fn execute(app: &TestApp, input: &str) -> pb::ExecuteResponse {
+    app.tool()
+        .execute(pb::ExecuteRequest {
+            input: input.as_bytes().to_vec(),
+            user: Some(pb::UserContext {
+                user_id: "u-1".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn tool_fn_receives_input_and_context() {
+    let schema = SchemaBuilder::new(&[]).string("text", "Text to repeat", true).build();
+    let echo = tool_fn("echo", "Repeats text.", schema, |input, ctx| async move {
+        Ok(format!("{} for {}", input["text"].as_str().unwrap(), ctx.user_id))
+    });
+    let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(echo))
+        .await
+        .unwrap();
+    let resp = execute(&app, r#"{"text":"hi"}"#).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(resp.content, "hi for u-1");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn typed_tool_fn_parses_input() {
+    let print = typed_tool_fn("print", "Prints a document.", |p: Print, _ctx| async move {
+        Ok(format!("{} x{}", p.name, p.copies))
+    });
+    let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(print))
+        .await
+        .unwrap();
+    let schema = app.tool().schema(pb::Empty {}).await.unwrap().into_inner();
+    let schema: serde_json::Value = serde_json::from_slice(&schema.schema).unwrap();
+    assert_eq!(schema["required"], serde_json::json!(["name", "copies"]));
+
+    let resp = execute(&app, r#"{"name":"report.pdf","copies":2}"#).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(resp.content, "report.pdf x2");
+
+    // 300 is an integer, as the schema asks, but does not fit in a `u8`.
+    let resp = execute(&app, r#"{"name":"report.pdf","copies":300}"#).await;
+    assert!(resp.is_error);
+    assert!(resp.content.contains("at `copies`"), "{}", resp.content);
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn ui_fn_renders_responses() {
+    let ui = ui_fn(|req: HttpRequest| async move {
+        Ok(HttpResponse {
+            status_code: 200,
+            headers: HashMap::from([("content-type".into(), "text/plain".into())]),
+            body: format!("{} {}?{}", req.method, req.path, req.query).into_bytes(),
+        })
+    });
+    let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_ui(ui))
+        .await
+        .unwrap();
+    let resp = app
+        .ui()
+        .handle_request(pb::HttpRequest {
+            method: "GET".into(),
+            path: "/reports".into(),
+            query: "page=2".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(resp.status_code, 200);
+    assert_eq!(resp.headers["content-type"], "text/plain");
+    assert_eq!(resp.body, b"GET /reports?page=2");
+    app.shutdown().await.unwrap();
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use nebo_sdk::gateway::{GatewayEvent, GatewayEventKind, GatewayHandler, GatewayRequest};
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{AppEnv, NeboApp, NeboError};
+use tokio::sync::mpsc;
+
+/// Replies with a fixed list of events.
+struct Replay(Vec<GatewayEvent>);
+
+#[async_trait]
+impl GatewayHandler for Replay {
+    async fn stream(&self, _req: GatewayRequest) -> Result<mpsc::Receiver<GatewayEvent>, NeboError> {
+        let (tx, rx) = mpsc::channel(self.0.len().max(1));
+        for event in &self.0 {
+            tx.send(event.clone()).await.unwrap();
+        }
+        Ok(rx)
+    }
+
+    async fn cancel(&self, _request_id: &str) -> Result<(), NeboError> {
+        Ok(())
+    }
+}
+
+async fn stream(events: Vec<GatewayEvent>) -> Vec<pb::GatewayEvent> {
+    let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_gateway(Replay(events)))
+        .await
+        .unwrap();
+    let req = pb::GatewayRequest {
+        request_id: "r1".into(),
+        ..Default::default()
+    };
+    let mut stream = app.gateway().stream(req).await.unwrap().into_inner();
+    let mut received = Vec::new();
+    while let Some(event) = stream.message().await.unwrap() {
+        received.push(event);
+    }
+    app.shutdown().await.unwrap();
+    received
+}
+
+#[tokio::test]
+async fn events_reach_nebo_as_sent() {
+    let events = stream(vec![
+        GatewayEvent::text("r1", "hi").with_model("m"),
+        GatewayEvent::usage("r1", 3, 5),
+        GatewayEvent::done("r1"),
+    ])
+    .await;
+    let kinds: Vec<_> = events.iter().map(|e| GatewayEventKind::try_from(e).unwrap()).collect();
+    assert_eq!(
+        kinds,
+        [
+            GatewayEventKind::Text("hi".into()),
+            GatewayEventKind::Usage {
+                input_tokens: 3,
+                output_tokens: 5
+            },
+            GatewayEventKind::Done,
+        ]
+    );
+    assert_eq!(events[0].model, "m");
+}
+
+#[tokio::test]
+async fn mistyped_events_become_errors() {
+    let mut unknown = GatewayEvent::text("r1", "hi").with_model("m");
+    unknown.r#type = "txt".into();
+    let mut malformed = GatewayEvent::done("r1");
+    malformed.r#type = "tool_call".into();
+    malformed.content = "not json".into();
+
+    let events = stream(vec![unknown, malformed, GatewayEvent::done("r1")]).await;
+    assert_eq!(events.len(), 3);
+    for event in &events[..2] {
+        assert_eq!(event.r#type, "error");
+        assert_eq!(event.request_id, "r1");
+    }
+    assert!(events[0].content.contains("unknown gateway event type `txt`"));
+    assert_eq!(events[0].model, "m");
+    assert!(events[1].content.contains("invalid tool_call event"));
+    assert_eq!(events[2].r#type, "done");
+}
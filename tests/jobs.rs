@@ -0,0 +1,114 @@
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{tool_fn, AppEnv, NeboApp, SchemaBuilder, ToolOutput};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tonic::Code;
+
+/// Starts a job that reports progress, then finishes once `release` is
+/// notified, or runs until cancelled if the input says `forever`.
+async fn start(release: Arc<Notify>) -> TestApp {
+    let schema = SchemaBuilder::new(&[]).boolean("forever", "Never finish", false).build();
+    let tool = tool_fn("report", "Builds a report.", schema, move |input, ctx| {
+        let release = release.clone();
+        async move {
+            let forever = input["forever"].as_bool().unwrap_or(false);
+            let job = ctx.spawn_job(move |job| async move {
+                job.progress(50.0, "halfway");
+                if forever {
+                    std::future::pending::<()>().await;
+                }
+                release.notified().await;
+                Ok(ToolOutput::text("report ready"))
+            });
+            Ok(job)
+        }
+    });
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool))
+        .await
+        .unwrap()
+}
+
+async fn spawn(app: &TestApp, input: &str) -> String {
+    let resp = app
+        .tool()
+        .execute(pb::ExecuteRequest {
+            input: input.as_bytes().to_vec(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!resp.is_error, "{}", resp.content);
+    assert!(!resp.job_id.is_empty());
+    resp.job_id
+}
+
+fn job(id: &str) -> pb::JobRequest {
+    pb::JobRequest { job_id: id.to_string() }
+}
+
+/// Poll `JobStatus` until `done` accepts the job's info.
+async fn wait_for(app: &TestApp, id: &str, done: impl Fn(&pb::JobInfo) -> bool) -> pb::JobInfo {
+    let poll = async {
+        loop {
+            let info = app.tool().job_status(job(id)).await.unwrap().into_inner();
+            if done(&info) {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), poll)
+        .await
+        .unwrap_or_else(|_| panic!("job {id} did not get there in time"))
+}
+
+#[tokio::test]
+async fn job_runs_to_completion() {
+    let release = Arc::new(Notify::new());
+    let app = start(release.clone()).await;
+    let id = spawn(&app, "{}").await;
+
+    let info = wait_for(&app, &id, |info| info.percent == 50.0).await;
+    assert_eq!((info.job_id.as_str(), info.tool_name.as_str()), (id.as_str(), "report"));
+    assert_eq!((info.state.as_str(), info.message.as_str()), ("running", "halfway"));
+    let status = app.tool().job_result(job(&id)).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    release.notify_one();
+    let info = wait_for(&app, &id, |info| info.state == "succeeded").await;
+    assert_eq!(info.percent, 100.0);
+    let result = app.tool().job_result(job(&id)).await.unwrap().into_inner();
+    assert!(!result.is_error);
+    assert_eq!(result.content, "report ready");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn running_job_can_be_cancelled() {
+    let app = start(Arc::new(Notify::new())).await;
+    let id = spawn(&app, r#"{"forever":true}"#).await;
+
+    let info = app.tool().cancel_job(job(&id)).await.unwrap().into_inner();
+    assert_eq!(info.state, "cancelled");
+    let result = app.tool().job_result(job(&id)).await.unwrap().into_inner();
+    assert!(result.is_error);
+    assert_eq!(result.error_code, "cancelled");
+
+    // Cancelling again changes nothing.
+    let info = app.tool().cancel_job(job(&id)).await.unwrap().into_inner();
+    assert_eq!(info.state, "cancelled");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_job_is_not_found() {
+    let app = start(Arc::new(Notify::new())).await;
+    let mut tool = app.tool();
+    assert_eq!(tool.job_status(job("job-nope")).await.unwrap_err().code(), Code::NotFound);
+    assert_eq!(tool.job_result(job("job-nope")).await.unwrap_err().code(), Code::NotFound);
+    assert_eq!(tool.cancel_job(job("job-nope")).await.unwrap_err().code(), Code::NotFound);
+    app.shutdown().await.unwrap();
+}
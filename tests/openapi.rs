@@ -0,0 +1,316 @@
+use nebo_sdk::openapi::OpenApiTool;
+use nebo_sdk::tool::ToolHandler;
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{AppEnv, NeboApp};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// One HTTP request as the mock server received it.
+#[derive(Debug)]
+struct Received {
+    method: String,
+    target: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// Serve HTTP/1.1 on a local port, answering every request with `{"ok":true}`
+/// except paths containing `missing`, which get a 404.
+async fn mock_server() -> (String, mpsc::UnboundedReceiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/v1", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let target = parts.next().unwrap_or_default().to_string();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.insert(name.to_lowercase(), value.trim().to_string());
+                }
+                let len = headers.get("content-length").map_or(0, |l| l.parse().unwrap());
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).await.unwrap();
+
+                let (status, reply) = if target.contains("missing") {
+                    ("404 Not Found", r#"{"error":"no such thing"}"#)
+                } else {
+                    ("200 OK", r#"{"ok":true}"#)
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                );
+                reader.get_mut().write_all(response.as_bytes()).await.unwrap();
+                let body = String::from_utf8(body).unwrap();
+                let _ = tx.send(Received { method, target, headers, body });
+            });
+        }
+    });
+    (url, rx)
+}
+
+fn spec() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {"title": "Shop", "version": "1"},
+        "servers": [{"url": "https://shop.example.com/v1"}],
+        "paths": {
+            "/customers": {
+                "post": {
+                    "operationId": "createCustomer",
+                    "requestBody": {
+                        "required": true,
+                        "content": {"application/json": {"schema": {
+                            "type": "object",
+                            "required": ["email"],
+                            "properties": {"email": {"type": "string"}},
+                        }}},
+                    },
+                    "parameters": [{"name": "id", "in": "query", "schema": {"type": "string"}}],
+                },
+            },
+            "/invoices": {
+                "get": {
+                    "operationId": "listInvoices",
+                    "summary": "List invoices",
+                    "parameters": [
+                        {"name": "status", "in": "query", "schema": {"type": "array", "items": {"type": "string"}}},
+                        {"name": "X-Trace", "in": "header", "schema": {"type": "string"}},
+                    ],
+                },
+                "post": {
+                    "operationId": "createInvoice",
+                    "requestBody": {
+                        "required": true,
+                        "content": {"application/json": {"schema": {"$ref": "#/components/schemas/NewInvoice"}}},
+                    },
+                },
+            },
+            "/orders/{id}": {
+                "get": {
+                    "operationId": "getOrder",
+                    "parameters": [{"name": "id", "in": "path", "schema": {"type": "integer"}}],
+                },
+            },
+            "/files/{name}": {
+                "get": {
+                    "operationId": "getFile",
+                    "parameters": [{"name": "name", "in": "path", "schema": {"type": "string"}}],
+                },
+            },
+        },
+        "components": {"schemas": {"NewInvoice": {
+            "type": "object",
+            "required": ["amount"],
+            "properties": {"amount": {"type": "integer"}, "memo": {"type": "string", "nullable": true}},
+        }}},
+    })
+}
+
+async fn start(url: &str) -> TestApp {
+    let tool = OpenApiTool::from_spec("shop", spec())
+        .unwrap()
+        .base_url(url)
+        .header("X-Api-Key", "secret")
+        .forward_user_token(true);
+    nebo_sdk::testing::assert_lint_clean(&tool);
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool))
+        .await
+        .unwrap()
+}
+
+async fn execute(app: &TestApp, input: Value) -> pb::ExecuteResponse {
+    app.tool()
+        .execute(pb::ExecuteRequest {
+            input: serde_json::to_vec(&input).unwrap(),
+            user: Some(pb::UserContext {
+                token: "user-jwt".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn sends_path_query_headers_body_and_token() {
+    let (url, mut requests) = mock_server().await;
+    let app = start(&url).await;
+
+    let resp = execute(
+        &app,
+        json!({"action": "listInvoices", "status": ["open", "paid"], "X-Trace": "t-1"}),
+    )
+    .await;
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(resp.metadata["http_status"], "200");
+    let req = requests.recv().await.unwrap();
+    assert_eq!(req.method, "GET");
+    assert_eq!(req.target, "/v1/invoices?status=open&status=paid");
+    assert_eq!(req.headers["x-trace"], "t-1");
+    assert_eq!(req.headers["x-api-key"], "secret");
+    assert_eq!(req.headers["authorization"], "Bearer user-jwt");
+
+    let resp = execute(&app, json!({"action": "createInvoice", "body": {"amount": 5}})).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    let req = requests.recv().await.unwrap();
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.target, "/v1/invoices");
+    assert_eq!(req.headers["content-type"], "application/json");
+    assert_eq!(serde_json::from_str::<Value>(&req.body).unwrap(), json!({"amount": 5}));
+
+    let resp = execute(&app, json!({"action": "getFile", "name": "a b/c"})).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(requests.recv().await.unwrap().target, "/v1/files/a%20b%2Fc");
+
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn operations_keep_their_own_body_and_parameter_schemas() {
+    let (url, mut requests) = mock_server().await;
+    let app = start(&url).await;
+
+    // `createCustomer` declares a different body and a string `id`.
+    let resp = execute(&app, json!({"action": "createInvoice", "body": {"amount": 5}})).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    requests.recv().await.unwrap();
+
+    let resp = execute(&app, json!({"action": "getOrder", "id": 7})).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(requests.recv().await.unwrap().target, "/v1/orders/7");
+
+    let resp = execute(&app, json!({"action": "createCustomer", "body": {"amount": 5}})).await;
+    assert!(resp.is_error);
+    assert!(
+        resp.content.contains("`body.email` is required when `action` is \"createCustomer\""),
+        "{}",
+        resp.content
+    );
+
+    let resp = execute(&app, json!({"action": "getOrder", "id": "seven"})).await;
+    assert!(resp.is_error);
+    assert!(resp.content.contains("`id` must be integer"), "{}", resp.content);
+
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn error_status_is_reported() {
+    let (url, _requests) = mock_server().await;
+    let app = start(&url).await;
+    let resp = execute(&app, json!({"action": "getFile", "name": "missing"})).await;
+    assert!(resp.is_error);
+    assert!(
+        resp.content.contains("GET /files/{name} returned 404 Not Found: {\"error\":\"no such thing\"}"),
+        "{}",
+        resp.content
+    );
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_operation_fails_to_start() {
+    let tool = OpenApiTool::from_spec("shop", spec())
+        .unwrap()
+        .operations(&["listInvoices", "refundInvoice"]);
+    let err = TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool))
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("refundInvoice"), "{err}");
+}
+
+/// An operation whose parameters clash with `action`, `body` and each other.
+fn clashing_spec(extra: Value) -> Value {
+    let mut params = vec![
+        json!({"name": "action", "in": "path", "schema": {"type": "string"}}),
+        json!({"name": "id", "in": "query", "schema": {"type": "string"}}),
+        json!({"name": "id", "in": "header", "schema": {"type": "string"}}),
+        json!({"name": "body", "in": "query", "schema": {"type": "string"}}),
+    ];
+    if !extra.is_null() {
+        params.push(extra);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {"title": "Jobs", "version": "1"},
+        "paths": {"/jobs/{action}": {"post": {
+            "operationId": "runJob",
+            "parameters": params,
+            "requestBody": {"content": {"application/json": {"schema": {"type": "object"}}}},
+        }}},
+    })
+}
+
+#[tokio::test]
+async fn clashing_parameters_are_prefixed_with_their_location() {
+    let (url, mut requests) = mock_server().await;
+    let tool = OpenApiTool::from_spec("jobs", clashing_spec(Value::Null)).unwrap().base_url(&url);
+    let props = tool.schema()["properties"].clone();
+    for name in ["path_action", "query_id", "header_id", "query_body", "body"] {
+        assert!(props.get(name).is_some(), "missing `{name}` in {props}");
+    }
+    assert!(props.get("id").is_none());
+    assert_eq!(props["action"]["enum"], json!(["runJob"]));
+
+    let app = TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool))
+        .await
+        .unwrap();
+    let input = json!({
+        "action": "runJob",
+        "path_action": "restart",
+        "query_id": "q",
+        "header_id": "h",
+        "query_body": "b",
+        "body": {"force": true},
+    });
+    let resp = execute(&app, input).await;
+    assert!(!resp.is_error, "{}", resp.content);
+    let req = requests.recv().await.unwrap();
+    assert_eq!(req.target, "/v1/jobs/restart?id=q&body=b");
+    assert_eq!(req.headers["id"], "h");
+    assert_eq!(serde_json::from_str::<Value>(&req.body).unwrap(), json!({"force": true}));
+    app.shutdown().await.unwrap();
+}
+
+#[test]
+fn unresolvable_parameter_clash_is_an_error() {
+    let extra = json!({"name": "query_id", "in": "query", "schema": {"type": "string"}});
+    let err = OpenApiTool::from_spec("jobs", clashing_spec(extra)).unwrap_err();
+    assert_eq!(err.to_string(), "operation `runJob` has more than one input named `query_id`");
+}
+
+#[test]
+fn loads_yaml_specs() {
+    let dir = std::env::temp_dir().join(format!("nebo-openapi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let yaml = "openapi: 3.0.3\ninfo:\n  title: Shop\n  version: '1'\npaths:\n  /orders/{id}:\n    get:\n      operationId: getOrder\n      parameters:\n        - {name: id, in: path, schema: {type: integer}}\n      responses:\n        200: {description: OK}\n";
+    std::fs::write(dir.join("shop.yaml"), yaml).unwrap();
+    let env = AppEnv {
+        dir: dir.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    let tool = OpenApiTool::load(&env, "shop", "shop.yaml").unwrap();
+    assert_eq!(tool.schema()["properties"]["id"]["type"], "integer");
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,115 @@
+use nebo_sdk::pb;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{tool_fn, AppEnv, NeboApp, NeboError, SchemaBuilder, ToolRegistry};
+use serde_json::Value;
+use tonic::Code;
+
+fn echo(name: &str, field: &str) -> nebo_sdk::tool::FnTool {
+    let schema = SchemaBuilder::new(&[]).string(field, "Text", true).build();
+    let field = field.to_string();
+    tool_fn(name, &format!("Echoes {field}."), schema, move |input, _ctx| {
+        let text = input[&field].as_str().unwrap_or_default().to_string();
+        async move { Ok(text) }
+    })
+}
+
+fn tools() -> ToolRegistry {
+    ToolRegistry::new()
+        .register(echo("first", "a"))
+        .register(echo("second", "b").requires_approval(true).reports_progress(true))
+}
+
+async fn start() -> TestApp {
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_tools(tools()))
+        .await
+        .unwrap()
+}
+
+fn named(name: &str) -> pb::ToolNameRequest {
+    pb::ToolNameRequest { name: name.to_string() }
+}
+
+#[tokio::test]
+async fn lists_every_tool() {
+    let app = start().await;
+    let list = app.tool().list_tools(pb::Empty {}).await.unwrap().into_inner();
+    let summary: Vec<_> = list
+        .tools
+        .iter()
+        .map(|t| (t.name.as_str(), t.description.as_str(), t.requires_approval, t.streaming))
+        .collect();
+    assert_eq!(
+        summary,
+        [("first", "Echoes a.", false, false), ("second", "Echoes b.", true, true)]
+    );
+    let schema: Value = serde_json::from_slice(&list.tools[1].schema).unwrap();
+    assert_eq!(schema["required"], serde_json::json!(["b"]));
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn looks_tools_up_by_name() {
+    let app = start().await;
+    let mut client = app.tool();
+    let schema = client.tool_schema(named("second")).await.unwrap().into_inner();
+    let schema: Value = serde_json::from_slice(&schema.schema).unwrap();
+    assert!(schema["properties"].get("b").is_some());
+
+    let approval = client.tool_requires_approval(named("second")).await.unwrap().into_inner();
+    assert!(approval.requires_approval);
+    let approval = client.tool_requires_approval(named("first")).await.unwrap().into_inner();
+    assert!(!approval.requires_approval);
+
+    // Requests without a tool name go to the first tool.
+    let name = client.name(pb::Empty {}).await.unwrap().into_inner();
+    assert_eq!(name.name, "first");
+    let resp = client
+        .execute(pb::ExecuteRequest {
+            tool_name: "second".into(),
+            input: br#"{"b":"hi"}"#.to_vec(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(resp.content, "hi");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_tool_is_not_found() {
+    let app = start().await;
+    let mut client = app.tool();
+    let status = client.tool_schema(named("third")).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    let status = client.tool_requires_approval(named("third")).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let resp = client
+        .execute(pb::ExecuteRequest {
+            tool_name: "third".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(resp.is_error);
+    assert_eq!(resp.content, "unknown tool: third");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn duplicate_names_fail_to_start() {
+    assert!(matches!(
+        tools().register(echo("first", "c")).check(),
+        Err(NeboError::DuplicateTool(name)) if name == "first"
+    ));
+    let app = NeboApp::with_env(AppEnv::default())
+        .register_tools(tools())
+        .register_tool(echo("second", "c"));
+    match TestApp::start(app).await {
+        Err(NeboError::DuplicateTool(name)) => assert_eq!(name, "second"),
+        Err(e) => panic!("expected a duplicate tool error, got {e}"),
+        Ok(_) => panic!("app started with duplicate tools"),
+    }
+}
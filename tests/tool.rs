@@ -0,0 +1,171 @@
+use nebo_sdk::pb;
+use nebo_sdk::pb::execute_event::Event;
+use nebo_sdk::testing::TestApp;
+use nebo_sdk::{tool_fn, AppEnv, NeboApp, SchemaBuilder};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tonic::{Code, Request};
+
+fn request(input: &str) -> pb::ExecuteRequest {
+    pb::ExecuteRequest {
+        input: input.as_bytes().to_vec(),
+        ..Default::default()
+    }
+}
+
+/// Adds `a` and `b`, reporting progress along the way.
+fn calc() -> impl nebo_sdk::tool::ToolHandler {
+    let schema = SchemaBuilder::new(&["add"])
+        .number("a", "First operand", true)
+        .number("b", "Second operand", true)
+        .build();
+    tool_fn("calc", "Adds numbers.", schema, |input, ctx| async move {
+        ctx.progress.progress(50.0, "adding");
+        Ok((input["a"].as_f64().unwrap() + input["b"].as_f64().unwrap()).to_string())
+    })
+}
+
+/// A tool that waits until its call is cancelled.
+fn hang() -> impl nebo_sdk::tool::ToolHandler {
+    tool_fn("hang", "Waits until cancelled.", SchemaBuilder::new(&[]).build(), |_, ctx| async move {
+        ctx.cancel.cancelled().await;
+        Ok("cancelled")
+    })
+}
+
+async fn start(tool: impl nebo_sdk::tool::ToolHandler) -> TestApp {
+    TestApp::start(NeboApp::with_env(AppEnv::default()).register_tool(tool))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn execute_honours_caller_deadline() {
+    let app = start(hang()).await;
+    let mut req = Request::new(request("{}"));
+    req.set_timeout(Duration::from_millis(300));
+    let started = Instant::now();
+    let resp = app.tool().execute(req).await.unwrap().into_inner();
+    assert!(resp.is_error);
+    assert_eq!(resp.error_code, "timeout");
+    assert!(started.elapsed() < Duration::from_millis(300));
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_stream_honours_caller_deadline() {
+    let app = start(hang()).await;
+    let mut req = Request::new(request("{}"));
+    req.set_timeout(Duration::from_millis(300));
+    let mut stream = app.tool().execute_stream(req).await.unwrap().into_inner();
+    let event = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("stream outlived the caller's deadline")
+        .unwrap()
+        .unwrap();
+    match event.event {
+        Some(pb::execute_event::Event::Result(resp)) => assert_eq!(resp.error_code, "timeout"),
+        other => panic!("expected a result, got {other:?}"),
+    }
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_returns_tool_output() {
+    let app = start(calc()).await;
+    let resp = app
+        .tool()
+        .execute(request(r#"{"action":"add","a":1,"b":2}"#))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!resp.is_error, "{}", resp.content);
+    assert_eq!(resp.content, "3");
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_rejects_invalid_input() {
+    let app = start(calc()).await;
+    let resp = app
+        .tool()
+        .execute(request(r#"{"action":"add","a":"1"}"#))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(resp.is_error);
+    assert!(resp.content.contains("`a` must be number, got string"), "{}", resp.content);
+    assert!(resp.content.contains("`b` is required"), "{}", resp.content);
+
+    let resp = app.tool().execute(request("{not json")).await.unwrap().into_inner();
+    assert!(resp.is_error);
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn execute_stream_sends_progress_then_result() {
+    let app = start(calc()).await;
+    let mut stream = app
+        .tool()
+        .execute_stream(request(r#"{"action":"add","a":2,"b":2}"#))
+        .await
+        .unwrap()
+        .into_inner();
+    let mut events = Vec::new();
+    while let Some(event) = stream.message().await.unwrap() {
+        events.push(event.event.unwrap());
+    }
+    match &events[..] {
+        [Event::Progress(p), Event::Result(r)] => {
+            assert_eq!((p.percent, p.message.as_str()), (50.0, "adding"));
+            assert_eq!(r.content, "4");
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+    app.shutdown().await.unwrap();
+}
+
+#[derive(Deserialize)]
+struct Config {
+    endpoint: String,
+    retries: u32,
+}
+
+#[tokio::test]
+async fn configure_applies_typed_settings() {
+    let app = NeboApp::with_env(AppEnv::default())
+        .typed_settings::<Config>()
+        .register_tool(calc());
+    let settings = app.settings();
+    let app = TestApp::start(app).await.unwrap();
+    let configure = |pairs: &[(&str, &str)]| pb::SettingsMap {
+        values: pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+    };
+
+    app.tool()
+        .configure(configure(&[("endpoint", "https://example.com"), ("retries", "3")]))
+        .await
+        .unwrap();
+    let config: Config = settings.parse().unwrap();
+    assert_eq!((config.endpoint.as_str(), config.retries), ("https://example.com", 3));
+
+    let status = app
+        .tool()
+        .configure(configure(&[("endpoint", "x"), ("retries", "many")]))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(status.message().contains("many"), "{}", status.message());
+    assert_eq!(settings.get("retries").as_deref(), Some("3"));
+    assert_eq!(settings.values().len(), 2);
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn socket_is_removed_on_shutdown() {
+    let app = start(calc()).await;
+    let sock = app.sock_path().to_path_buf();
+    assert!(sock.exists());
+    app.shutdown().await.unwrap();
+    assert!(!sock.exists());
+}